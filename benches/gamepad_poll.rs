@@ -0,0 +1,105 @@
+//! Measures a steady-state controller poll (`osContStartReadData` followed by
+//! `osContGetReadData` and `osContGetQuery`) and checks it doesn't allocate.
+//!
+//! Run with `cargo bench --bench gamepad_poll`.
+
+use helix::gamepad::bindings::BindingMode;
+use helix::gamepad::manager::{GamepadManager, Pads, Statuses};
+use helix::gamepad::providers::gilrs::GirlsGamepadProvider;
+use helix::gamepad::providers::keyboard::KeyboardGamepadProvider;
+use helix::gamepad::providers::mouse::MouseGamepadProvider;
+use helix::gamepad::providers::GamepadProvider;
+use helix::gamepad::types::{N64Button, MAXCONTROLLERS};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+const WARMUP_POLLS: usize = 120;
+const POLLS: usize = 100_000;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn main() {
+    // the same providers as the game, gilrs only when the platform has a backend
+    let mut providers: Vec<Box<dyn GamepadProvider>> = Vec::new();
+    match GirlsGamepadProvider::new() {
+        Ok(provider) => providers.push(Box::new(provider)),
+        Err(e) => println!("Polling without gilrs, the gamepad backend is unavailable: {e}"),
+    }
+    providers.push(Box::new(KeyboardGamepadProvider::new()));
+    providers.push(Box::new(MouseGamepadProvider::new()));
+
+    let mut manager = GamepadManager::with_providers(providers);
+
+    let virtual_gamepads = manager.virtual_gamepads();
+    for slot in 1..MAXCONTROLLERS {
+        manager.connect_virtual_gamepad(slot);
+    }
+    manager.init();
+
+    // goes through the bindings layer too
+    manager.set_button_behavior(1, N64Button::A, Some(BindingMode::Turbo { half_period: 2 }));
+    manager.set_button_behavior(2, N64Button::Z, Some(BindingMode::Toggle));
+
+    let mut pads = Pads::default();
+    let mut statuses = Statuses::default();
+    let mut poll = |frame: usize| {
+        for slot in 1..MAXCONTROLLERS {
+            virtual_gamepads.set_stick(slot, (frame % 160) as i8 - 80, 40);
+            if frame % 8 < 4 {
+                virtual_gamepads.press(slot, N64Button::A);
+                virtual_gamepads.press(slot, N64Button::Z);
+            } else {
+                virtual_gamepads.release(slot, N64Button::A);
+                virtual_gamepads.release(slot, N64Button::Z);
+            }
+        }
+
+        manager.process_events();
+        manager.read(&mut pads);
+        manager.query(&mut statuses);
+        black_box((&pads, &statuses));
+    };
+
+    // lets the buffers that grow on first use reach their final size
+    for frame in 0..WARMUP_POLLS {
+        poll(frame);
+    }
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for frame in 0..POLLS {
+        poll(frame);
+    }
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    println!(
+        "{POLLS} polls in {elapsed:.2?} ({:.3} µs/poll), {allocations} allocations",
+        elapsed.as_secs_f64() * 1_000_000.0 / POLLS as f64
+    );
+
+    assert_eq!(allocations, 0, "steady-state polling allocated");
+}
@@ -17,6 +17,23 @@ void HLXInit() {
     _osContInternalSetup(_gamepad_manager);
 }
 
+void HLXShutdown() {
+    // the gui borrows the gamepad manager and the event loop, it goes first
+    GUIFree(_gui);
+    _gui = NULL;
+
+    if (_audio_player != NULL) {
+        AudioPlayerFree(_audio_player);
+        _audio_player = NULL;
+    }
+
+    GamepadManagerFree(_gamepad_manager);
+    _gamepad_manager = NULL;
+
+    GUIFreeEventLoop(_event_loop);
+    _event_loop = NULL;
+}
+
 // Audio
 void HLXAudioSetup(uint32_t sampleRate, uint16_t channels) {
     _audio_player = AudioPlayerCreate(32000, 2);
@@ -34,9 +51,138 @@ void HLXAudioPlayBuffer(const uint8_t* buf, size_t len) {
     AudioPlayerQueueBuffer(_audio_player, buf, len);
 }
 
+// Gamepad
+s32 HLXGamepadLoadMappingProfile(const char* path) {
+    return GamepadManagerLoadMappingProfile(_gamepad_manager, path);
+}
+
+s32 HLXGamepadSaveMappingProfile(const char* path) {
+    return GamepadManagerSaveMappingProfile(_gamepad_manager, path);
+}
+
+s32 HLXGamepadLoadKeyboardMapping(const char* path) {
+    return GamepadManagerLoadKeyboardMapping(_gamepad_manager, path);
+}
+
+s32 HLXGamepadSaveKeyboardMapping(const char* path) {
+    return GamepadManagerSaveKeyboardMapping(_gamepad_manager, path);
+}
+
+s32 HLXGamepadLoadDeviceProfiles(const char* path) {
+    return GamepadManagerLoadDeviceProfiles(_gamepad_manager, path);
+}
+
+s32 HLXGamepadLoadControllerDatabase(const char* path) {
+    return GamepadManagerLoadControllerDatabase(_gamepad_manager, path);
+}
+
+HLXStickSettings HLXGamepadGetStickSettings(u8 port) {
+    return GamepadManagerGetStickSettings(_gamepad_manager, port);
+}
+
+void HLXGamepadSetStickSettings(u8 port, HLXStickSettings settings) {
+    GamepadManagerSetStickSettings(_gamepad_manager, port, settings);
+}
+
+void HLXGamepadSetRumbleStrength(float strength) {
+    GamepadManagerSetRumbleStrength(_gamepad_manager, strength);
+}
+
+s32 HLXGamepadInsertControllerPak(u8 port, const char* path) {
+    return GamepadManagerInsertControllerPak(_gamepad_manager, port, path);
+}
+
+void HLXGamepadRemoveControllerPak(u8 port) {
+    GamepadManagerRemoveControllerPak(_gamepad_manager, port);
+}
+
+s32 HLXGamepadStartRecording(const char* path, const char* game_id) {
+    return GamepadManagerStartRecording(_gamepad_manager, path, game_id);
+}
+
+s32 HLXGamepadStopRecording() {
+    return GamepadManagerStopRecording(_gamepad_manager);
+}
+
+s32 HLXGamepadStartPlayback(const char* path) {
+    return GamepadManagerStartPlayback(_gamepad_manager, path);
+}
+
+void HLXGamepadStopPlayback() {
+    GamepadManagerStopPlayback(_gamepad_manager);
+}
+
+bool HLXGamepadIsPlayingBack() {
+    return GamepadManagerIsPlayingBack(_gamepad_manager);
+}
+
+void HLXGamepadConnectVirtual(u8 port) {
+    GamepadManagerConnectVirtualGamepad(_gamepad_manager, port);
+}
+
+void HLXGamepadDisconnectVirtual(u8 port) {
+    GamepadManagerDisconnectVirtualGamepad(_gamepad_manager, port);
+}
+
+void HLXGamepadSetVirtualState(u8 port, u16 buttons, s8 stick_x, s8 stick_y) {
+    GamepadManagerSetVirtualGamepadState(_gamepad_manager, port, buttons, stick_x, stick_y);
+}
+
+void HLXGamepadSetMousePort(s32 port) {
+    GamepadManagerSetMouseSlot(_gamepad_manager, port);
+}
+
+void HLXGamepadSetMouseSensitivity(float sensitivity) {
+    GamepadManagerSetMouseSensitivity(_gamepad_manager, sensitivity);
+}
+
+void HLXGamepadSetMouseLook(HLXMouseLookMode mode, float sensitivity, float smoothing, float decay) {
+    GamepadManagerSetMouseLook(_gamepad_manager, mode, sensitivity, smoothing, decay);
+}
+
+s32 HLXGamepadSetTurbo(u8 port, u16 button, u32 half_period) {
+    return GamepadManagerSetTurbo(_gamepad_manager, port, button, half_period);
+}
+
+s32 HLXGamepadSetToggle(u8 port, u16 button, bool enabled) {
+    return GamepadManagerSetToggle(_gamepad_manager, port, button, enabled);
+}
+
+void HLXGamepadStartMacroRecording(u8 port) {
+    GamepadManagerStartMacroRecording(_gamepad_manager, port);
+}
+
+s32 HLXGamepadFinishMacroRecording(u16 button) {
+    return GamepadManagerFinishMacroRecording(_gamepad_manager, button);
+}
+
+const char* HLXGamepadGetLastError() {
+    return GamepadManagerGetLastError(_gamepad_manager);
+}
+
+s32 HLXGamepadGetBackendStatus() {
+    return GamepadManagerGetBackendStatus(_gamepad_manager);
+}
+
+bool HLXGamepadPollEvent(HLXControllerEvent* event) {
+    return GamepadManagerPollControllerEvent(_gamepad_manager, event);
+}
+
+void HLXGamepadSetEventCallback(void (*callback)(HLXControllerEvent)) {
+    GamepadManagerSetControllerEventCallback(_gamepad_manager, callback);
+}
+
 // Window & Graphics
+HLXWindowConfig HLXDisplayDefaultConfig() {
+    return GUIDefaultWindowConfig();
+}
+
 void HLXDisplaySetup(const char* title, void (*draw_menu)(void*), void (*draw_windows)(void*)) {
-    _gui = GUICreate(title, _event_loop, draw_menu, draw_windows, _gamepad_manager); // pass in a possible keyboard observing object
+    HLXDisplaySetupWithConfig(title, NULL, draw_menu, draw_windows);
+}
+
+void HLXDisplaySetupWithConfig(const char* title, const HLXWindowConfig* config, void (*draw_menu)(void*), void (*draw_windows)(void*)) {
+    _gui = GUICreate(title, config, _event_loop, draw_menu, draw_windows, _gamepad_manager); // pass in a possible keyboard observing object
 }
 
 void HLXDisplayStartFrame() {
@@ -55,6 +201,94 @@ f32 HLXDisplayGetAspectRatio() {
     return GUIGetAspectRatio(_gui);
 }
 
+void HLXDisplaySetFrameRate(f64 rate) {
+    GUISetFrameRate(_gui, rate);
+}
+
+f64 HLXDisplayGetFrameRate() {
+    return GUIGetFrameRate(_gui);
+}
+
+HLXFrameStats HLXDisplayGetFrameStats() {
+    return GUIGetFrameStats(_gui);
+}
+
+bool HLXDisplayShouldClose() {
+    return GUIShouldClose(_gui);
+}
+
+void HLXDisplayRequestClose() {
+    GUIRequestClose(_gui);
+}
+
+void HLXDisplayCancelClose() {
+    GUICancelClose(_gui);
+}
+
+void HLXDisplaySetConfirmClose(bool confirm) {
+    GUISetConfirmClose(_gui, confirm);
+}
+
+bool HLXDisplayShouldRunGameFrame() {
+    return GUIShouldRunGameFrame(_gui);
+}
+
+void HLXDisplaySetPaused(bool paused) {
+    GUISetPaused(_gui, paused);
+}
+
+bool HLXDisplayIsPaused() {
+    return GUIIsPaused(_gui);
+}
+
+void HLXDisplayAdvanceFrame() {
+    GUIAdvanceFrame(_gui);
+}
+
+void HLXDisplaySetFastForward(bool fast_forward) {
+    GUISetFastForward(_gui, fast_forward);
+}
+
+bool HLXDisplayIsFastForwarding() {
+    return GUIIsFastForwarding(_gui);
+}
+
+void HLXDisplaySetFastForwardSpeed(f64 speed) {
+    GUISetFastForwardSpeed(_gui, speed);
+}
+
+void HLXDisplayToggleFullscreen() {
+    GUIToggleFullscreen(_gui);
+}
+
+void HLXDisplaySetFullscreen(bool fullscreen) {
+    GUISetFullscreen(_gui, fullscreen);
+}
+
+bool HLXDisplayIsFullscreen() {
+    return GUIIsFullscreen(_gui);
+}
+
+void HLXDisplayGetWindowSize(u32* width, u32* height) {
+    GUIGetWindowSize(_gui, width, height);
+}
+
+void HLXDisplaySetWindowSize(u32 width, u32 height) {
+    GUISetWindowSize(_gui, width, height);
+}
+
+s32 HLXDisplaySaveWindowState() {
+    return GUISaveWindowState(_gui);
+}
+
 void HLXShowProfilerWindow(void* ui, bool* opened) {
     GUIShowProfilerWindow(ui, _gui, opened);
 }
+
+void HLXShowControllerWindow(void* ui, bool* opened) {
+    GUIShowControllerWindow(ui, _gui, opened);
+}
+
+void HLXShowInputDisplay(void* ui, bool* opened, HLXInputDisplayPosition position, float opacity) {
+    GUIShowInputDisplay(ui, _gui, opened, position, opacity);
+}
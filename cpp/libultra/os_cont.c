@@ -14,7 +14,24 @@ void _osContInternalSetup(void* gamepad_manager) {
 // MARK: - Methods from libultra
 
 s32 osContInit(OSMesgQueue *mq, u8 *controller_bits, OSContStatus *status) {
-    return GamepadManagerInit(_ref_gamepad_manager, controller_bits);
+    s32 ret = GamepadManagerInit(_ref_gamepad_manager, controller_bits);
+    GamepadManagerGetQuery(_ref_gamepad_manager, status);
+    return ret;
+}
+
+s32 osContReset(OSMesgQueue *mq, OSContStatus *status) {
+    GamepadManagerReset(_ref_gamepad_manager);
+    GamepadManagerGetQuery(_ref_gamepad_manager, status);
+    return 0;
+}
+
+s32 osContStartQuery(OSMesgQueue *mq) {
+    GamepadManagerStartQuery(_ref_gamepad_manager);
+    return 0;
+}
+
+void osContGetQuery(OSContStatus *status) {
+    GamepadManagerGetQuery(_ref_gamepad_manager, status);
 }
 
 s32 osContStartReadData(OSMesgQueue *mesg) {
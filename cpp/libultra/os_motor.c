@@ -0,0 +1,28 @@
+#include <libultra/os_motor.h>
+#include <helix/internal.h>
+
+// Reference to the gamepad manager, set up by os_cont.c
+extern void *_ref_gamepad_manager;
+
+// MARK: - Methods from libultra
+
+s32 osMotorInit(OSMesgQueue *mq, OSPfs *pfs, int channel) {
+    pfs->queue = mq;
+    pfs->channel = channel;
+    pfs->status = 0;
+
+    s32 ret = GamepadManagerMotorInit(_ref_gamepad_manager, channel);
+    if (ret == 0) {
+        pfs->status |= PFS_MOTOR_INITIALIZED;
+    }
+
+    return ret;
+}
+
+s32 osMotorStart(OSPfs *pfs) {
+    return GamepadManagerMotorSet(_ref_gamepad_manager, pfs->channel, true);
+}
+
+s32 osMotorStop(OSPfs *pfs) {
+    return GamepadManagerMotorSet(_ref_gamepad_manager, pfs->channel, false);
+}
@@ -0,0 +1,83 @@
+#include <libultra/os_pfs.h>
+#include <helix/internal.h>
+
+// Reference to the gamepad manager, set up by os_cont.c
+extern void *_ref_gamepad_manager;
+
+// MARK: - Methods from libultra
+
+s32 osPfsIsPlug(OSMesgQueue *mq, u8 *pattern) {
+    *pattern = ControllerPakIsPlugged(_ref_gamepad_manager);
+    return 0;
+}
+
+s32 osPfsInitPak(OSMesgQueue *mq, OSPfs *pfs, int channel) {
+    pfs->queue = mq;
+    pfs->channel = channel;
+    pfs->status = 0;
+    pfs->banks = 1;
+    pfs->activebank = 0;
+    pfs->inode_start_page = 5;
+    pfs->dir_size = 16;
+
+    s32 ret = ControllerPakInit(_ref_gamepad_manager, channel);
+    if (ret == 0) {
+        pfs->status |= PFS_INITIALIZED;
+    }
+
+    return ret;
+}
+
+s32 osPfsInit(OSMesgQueue *mq, OSPfs *pfs, int channel) {
+    return osPfsInitPak(mq, pfs, channel);
+}
+
+s32 osPfsRepairId(OSPfs *pfs) {
+    return ControllerPakRepairId(_ref_gamepad_manager, pfs->channel);
+}
+
+s32 osPfsReFormat(OSPfs *pfs, OSMesgQueue *mq, int channel) {
+    pfs->queue = mq;
+    pfs->channel = channel;
+    return ControllerPakFormat(_ref_gamepad_manager, channel);
+}
+
+s32 osPfsChecker(OSPfs *pfs) {
+    return ControllerPakInit(_ref_gamepad_manager, pfs->channel);
+}
+
+s32 osPfsAllocateFile(OSPfs *pfs, u16 company_code, u32 game_code, u8 *game_name, u8 *ext_name, int file_size_in_bytes, s32 *file_no) {
+    return ControllerPakAllocateFile(_ref_gamepad_manager, pfs->channel, company_code, game_code, game_name, ext_name, file_size_in_bytes, file_no);
+}
+
+s32 osPfsFindFile(OSPfs *pfs, u16 company_code, u32 game_code, u8 *game_name, u8 *ext_name, s32 *file_no) {
+    return ControllerPakFindFile(_ref_gamepad_manager, pfs->channel, company_code, game_code, game_name, ext_name, file_no);
+}
+
+s32 osPfsDeleteFile(OSPfs *pfs, u16 company_code, u32 game_code, u8 *game_name, u8 *ext_name) {
+    return ControllerPakDeleteFile(_ref_gamepad_manager, pfs->channel, company_code, game_code, game_name, ext_name);
+}
+
+s32 osPfsReadWriteFile(OSPfs *pfs, s32 file_no, u8 flag, int offset, int size_in_bytes, u8 *data_buffer) {
+    return ControllerPakReadWriteFile(_ref_gamepad_manager, pfs->channel, file_no, flag == PFS_WRITE, offset, size_in_bytes, data_buffer);
+}
+
+s32 osPfsFileState(OSPfs *pfs, s32 file_no, OSPfsState *state) {
+    return ControllerPakFileState(_ref_gamepad_manager, pfs->channel, file_no, state);
+}
+
+s32 osPfsGetLabel(OSPfs *pfs, u8 *label, int *len) {
+    return ControllerPakGetLabel(_ref_gamepad_manager, pfs->channel, label, len);
+}
+
+s32 osPfsSetLabel(OSPfs *pfs, u8 *label) {
+    return ControllerPakSetLabel(_ref_gamepad_manager, pfs->channel, label);
+}
+
+s32 osPfsFreeBlocks(OSPfs *pfs, s32 *bytes_not_used) {
+    return ControllerPakFreeBlocks(_ref_gamepad_manager, pfs->channel, bytes_not_used);
+}
+
+s32 osPfsNumFiles(OSPfs *pfs, s32 *max_files, s32 *files_used) {
+    return ControllerPakNumFiles(_ref_gamepad_manager, pfs->channel, max_files, files_used);
+}
@@ -0,0 +1,82 @@
+#ifndef HELIX_LIB_GAMEPAD_H
+#define HELIX_LIB_GAMEPAD_H
+
+#include <stdint.h>
+#include <stdbool.h>
+
+#include <libultra/ultratypes.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum {
+    HLXGamepadErrorFailed = -1,
+    HLXGamepadErrorBackendUnavailable = -2,
+    HLXGamepadErrorInvalidArgument = -3,
+} HLXGamepadError;
+
+typedef struct {
+    float inner_deadzone;
+    float outer_deadzone;
+    float response_curve;
+    float range_scale;
+    bool octagon_gate;
+} HLXStickSettings;
+
+typedef enum {
+    HLXMouseLookOff,
+    HLXMouseLookStick,
+    HLXMouseLookCButtons,
+} HLXMouseLookMode;
+
+typedef struct {
+    u8 port;
+    bool connected;
+} HLXControllerEvent;
+
+s32 HLXGamepadLoadMappingProfile(const char* path);
+s32 HLXGamepadSaveMappingProfile(const char* path);
+s32 HLXGamepadLoadKeyboardMapping(const char* path);
+s32 HLXGamepadSaveKeyboardMapping(const char* path);
+s32 HLXGamepadLoadDeviceProfiles(const char* path);
+s32 HLXGamepadLoadControllerDatabase(const char* path);
+
+HLXStickSettings HLXGamepadGetStickSettings(u8 port);
+void HLXGamepadSetStickSettings(u8 port, HLXStickSettings settings);
+
+void HLXGamepadSetRumbleStrength(float strength);
+
+s32 HLXGamepadInsertControllerPak(u8 port, const char* path);
+void HLXGamepadRemoveControllerPak(u8 port);
+
+s32 HLXGamepadStartRecording(const char* path, const char* game_id);
+s32 HLXGamepadStopRecording();
+s32 HLXGamepadStartPlayback(const char* path);
+void HLXGamepadStopPlayback();
+bool HLXGamepadIsPlayingBack();
+
+void HLXGamepadConnectVirtual(u8 port);
+void HLXGamepadDisconnectVirtual(u8 port);
+void HLXGamepadSetVirtualState(u8 port, u16 buttons, s8 stick_x, s8 stick_y);
+
+void HLXGamepadSetMousePort(s32 port);
+void HLXGamepadSetMouseSensitivity(float sensitivity);
+void HLXGamepadSetMouseLook(HLXMouseLookMode mode, float sensitivity, float smoothing, float decay);
+
+s32 HLXGamepadSetTurbo(u8 port, u16 button, u32 half_period);
+s32 HLXGamepadSetToggle(u8 port, u16 button, bool enabled);
+void HLXGamepadStartMacroRecording(u8 port);
+s32 HLXGamepadFinishMacroRecording(u16 button);
+
+const char* HLXGamepadGetLastError();
+s32 HLXGamepadGetBackendStatus();
+
+bool HLXGamepadPollEvent(HLXControllerEvent* event);
+void HLXGamepadSetEventCallback(void (*callback)(HLXControllerEvent));
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* HELIX_LIB_GAMEPAD_H */
@@ -10,13 +10,79 @@
 extern "C" {
 #endif
 
+typedef enum {
+    HLXFullscreenOff,
+    HLXFullscreenBorderless,
+    HLXFullscreenExclusive,
+} HLXFullscreenMode;
+
+typedef enum {
+    HLXPresentVsync,
+    HLXPresentImmediate,
+    HLXPresentMailbox,
+} HLXPresentMode;
+
+typedef struct {
+    u32 width;
+    u32 height;
+    bool has_position;
+    s32 x;
+    s32 y;
+    s32 fullscreen; // HLXFullscreenMode
+    s32 monitor;
+    s32 present_mode; // HLXPresentMode
+    bool resizable;
+    u32 min_width;
+    u32 min_height;
+    bool save_state;
+} HLXWindowConfig;
+
+typedef struct {
+    f32 last;
+    f32 average;
+    f32 min;
+    f32 max;
+} HLXFrameStats;
+
+typedef enum {
+    HLXInputDisplayTopLeft,
+    HLXInputDisplayTopRight,
+    HLXInputDisplayBottomLeft,
+    HLXInputDisplayBottomRight,
+    HLXInputDisplayFree,
+} HLXInputDisplayPosition;
+
+HLXWindowConfig HLXDisplayDefaultConfig();
 void HLXDisplaySetup(const char* title, void (*draw_menu)(), void (*draw_windows)());
+void HLXDisplaySetupWithConfig(const char* title, const HLXWindowConfig* config, void (*draw_menu)(), void (*draw_windows)());
 void HLXDisplayStartFrame();
 void HLXDisplayProcessDrawLists(u64* commands);
 void HLXDisplayEndFrame();
 float HLXDisplayGetAspectRatio();
+void HLXDisplaySetFrameRate(f64 rate);
+f64 HLXDisplayGetFrameRate();
+HLXFrameStats HLXDisplayGetFrameStats();
+bool HLXDisplayShouldClose();
+void HLXDisplayRequestClose();
+void HLXDisplayCancelClose();
+void HLXDisplaySetConfirmClose(bool confirm);
+bool HLXDisplayShouldRunGameFrame();
+void HLXDisplaySetPaused(bool paused);
+bool HLXDisplayIsPaused();
+void HLXDisplayAdvanceFrame();
+void HLXDisplaySetFastForward(bool fast_forward);
+bool HLXDisplayIsFastForwarding();
+void HLXDisplaySetFastForwardSpeed(f64 speed);
+void HLXDisplayToggleFullscreen();
+void HLXDisplaySetFullscreen(bool fullscreen);
+bool HLXDisplayIsFullscreen();
+void HLXDisplayGetWindowSize(u32* width, u32* height);
+void HLXDisplaySetWindowSize(u32 width, u32 height);
+s32 HLXDisplaySaveWindowState();
 
 void HLXShowProfilerWindow(void* ui, bool* opened);
+void HLXShowControllerWindow(void* ui, bool* opened);
+void HLXShowInputDisplay(void* ui, bool* opened, HLXInputDisplayPosition position, float opacity);
 
 #ifdef __cplusplus
 }
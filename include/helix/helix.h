@@ -5,12 +5,14 @@
 #include "audio.h"
 #include "network.h"
 #include "gui.h"
+#include "gamepad.h"
 
 #ifdef __cplusplus
 extern "C" {
 #endif
 
 void HLXInit();
+void HLXShutdown();
 
 #ifdef __cplusplus
 }
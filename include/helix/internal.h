@@ -6,7 +6,10 @@
 #include <stdbool.h>
 
 #include <libultra/os_cont.h>
+#include <libultra/os_pfs.h>
 #include <libultra/ultratypes.h>
+#include <helix/gamepad.h>
+#include <helix/gui.h>
 
 #ifdef __cplusplus
 extern "C" {
@@ -30,21 +33,98 @@ void AudioPlayerQueueBuffer(void* player, const uint8_t* buf, size_t len);
 
 // GUI
 void* GUICreateEventLoop(void);
-void* GUICreate(const char* title, void* event_loop, void (*draw_menu_callback)(void*), void (*draw_windows_callback)(void*), void* gamepad_manager);
+void GUIFreeEventLoop(void* event_loop);
+HLXWindowConfig GUIDefaultWindowConfig(void);
+void* GUICreate(const char* title, const HLXWindowConfig* config, void* event_loop, void (*draw_menu_callback)(void*), void (*draw_windows_callback)(void*), void* gamepad_manager);
+void GUIFree(void* gui);
 void GUIStartFrame(void* gui, void* event_loop);
 void GUIDrawLists(void* gui, uint64_t* commands);
 void GUIEndFrame(void* gui);
 
 f32 GUIGetAspectRatio(void* gui);
+void GUISetFrameRate(void* gui, f64 rate);
+f64 GUIGetFrameRate(void* gui);
+HLXFrameStats GUIGetFrameStats(void* gui);
+bool GUIShouldRunGameFrame(void* gui);
+void GUISetPaused(void* gui, bool paused);
+bool GUIIsPaused(void* gui);
+void GUIAdvanceFrame(void* gui);
+void GUISetFastForward(void* gui, bool fast_forward);
+bool GUIIsFastForwarding(void* gui);
+void GUISetFastForwardSpeed(void* gui, f64 speed);
+bool GUIShouldClose(void* gui);
+void GUIRequestClose(void* gui);
+void GUICancelClose(void* gui);
+void GUISetConfirmClose(void* gui, bool confirm);
+void GUIToggleFullscreen(void* gui);
+void GUISetFullscreen(void* gui, bool fullscreen);
+bool GUIIsFullscreen(void* gui);
+void GUIGetWindowSize(void* gui, u32* width, u32* height);
+void GUISetWindowSize(void* gui, u32 width, u32 height);
+s32 GUISaveWindowState(void* gui);
 
 void GUIShowProfilerWindow(void* ui, void* gui, bool* opened);
+void GUIShowControllerWindow(void* ui, void* gui, bool* opened);
+void GUIShowInputDisplay(void* ui, void* gui, bool* opened, s32 position, float opacity);
 
 // Gamepad
 
 void* GamepadManagerCreate(void);
+void GamepadManagerFree(void* manager);
 s32 GamepadManagerInit(void* manager, u8* gamepad_bits);
 void GamepadManagerProcessEvents(void* manager);
-void GamepadManagerGetReadData(void* manager, OSContPad* pad);
+void GamepadManagerGetReadData(void* manager, OSContPad* pads);
+void GamepadManagerStartQuery(void* manager);
+void GamepadManagerGetQuery(void* manager, OSContStatus* statuses);
+void GamepadManagerReset(void* manager);
+s32 GamepadManagerLoadMappingProfile(void* manager, const char* path);
+s32 GamepadManagerSaveMappingProfile(void* manager, const char* path);
+s32 GamepadManagerLoadKeyboardMapping(void* manager, const char* path);
+s32 GamepadManagerSaveKeyboardMapping(void* manager, const char* path);
+s32 GamepadManagerLoadDeviceProfiles(void* manager, const char* path);
+s32 GamepadManagerLoadControllerDatabase(void* manager, const char* path);
+HLXStickSettings GamepadManagerGetStickSettings(void* manager, u8 port);
+void GamepadManagerSetStickSettings(void* manager, u8 port, HLXStickSettings settings);
+s32 GamepadManagerMotorInit(void* manager, s32 port);
+s32 GamepadManagerMotorSet(void* manager, s32 port, bool enabled);
+void GamepadManagerSetRumbleStrength(void* manager, float strength);
+s32 GamepadManagerInsertControllerPak(void* manager, s32 port, const char* path);
+void GamepadManagerRemoveControllerPak(void* manager, s32 port);
+s32 GamepadManagerStartRecording(void* manager, const char* path, const char* game_id);
+s32 GamepadManagerStopRecording(void* manager);
+s32 GamepadManagerStartPlayback(void* manager, const char* path);
+void GamepadManagerStopPlayback(void* manager);
+bool GamepadManagerIsPlayingBack(void* manager);
+void GamepadManagerConnectVirtualGamepad(void* manager, u8 port);
+void GamepadManagerDisconnectVirtualGamepad(void* manager, u8 port);
+void GamepadManagerSetVirtualGamepadState(void* manager, u8 port, u16 buttons, s8 stick_x, s8 stick_y);
+void GamepadManagerSetMouseSlot(void* manager, s32 port);
+void GamepadManagerSetMouseSensitivity(void* manager, float sensitivity);
+void GamepadManagerSetMouseLook(void* manager, s32 mode, float sensitivity, float smoothing, float decay);
+s32 GamepadManagerSetTurbo(void* manager, u8 port, u16 button, u32 half_period);
+s32 GamepadManagerSetToggle(void* manager, u8 port, u16 button, bool enabled);
+void GamepadManagerStartMacroRecording(void* manager, u8 port);
+s32 GamepadManagerFinishMacroRecording(void* manager, u16 button);
+const char* GamepadManagerGetLastError(void* manager);
+s32 GamepadManagerGetBackendStatus(void* manager);
+bool GamepadManagerPollControllerEvent(void* manager, HLXControllerEvent* event);
+void GamepadManagerSetControllerEventCallback(void* manager, void (*callback)(HLXControllerEvent));
+
+// Controller Pak
+
+u8 ControllerPakIsPlugged(void* manager);
+s32 ControllerPakInit(void* manager, s32 port);
+s32 ControllerPakRepairId(void* manager, s32 port);
+s32 ControllerPakFormat(void* manager, s32 port);
+s32 ControllerPakAllocateFile(void* manager, s32 port, u16 company_code, u32 game_code, u8* game_name, u8* ext_name, s32 size, s32* file_no);
+s32 ControllerPakFindFile(void* manager, s32 port, u16 company_code, u32 game_code, u8* game_name, u8* ext_name, s32* file_no);
+s32 ControllerPakDeleteFile(void* manager, s32 port, u16 company_code, u32 game_code, u8* game_name, u8* ext_name);
+s32 ControllerPakReadWriteFile(void* manager, s32 port, s32 file_no, bool write, s32 offset, s32 size, u8* buffer);
+s32 ControllerPakFileState(void* manager, s32 port, s32 file_no, OSPfsState* state);
+s32 ControllerPakFreeBlocks(void* manager, s32 port, s32* bytes_not_used);
+s32 ControllerPakNumFiles(void* manager, s32 port, s32* max_files, s32* files_used);
+s32 ControllerPakGetLabel(void* manager, s32 port, u8* label, s32* len);
+s32 ControllerPakSetLabel(void* manager, s32 port, u8* label);
 
 #ifdef __cplusplus
 }
@@ -0,0 +1,34 @@
+#ifndef _OS_MOTOR_H_
+#define	_OS_MOTOR_H_
+
+#ifdef _LANGUAGE_C_PLUS_PLUS
+extern "C" {
+#endif
+
+#include <libultra/ultratypes.h>
+#include <libultra/os_message.h>
+#include <libultra/os_pfs.h>
+
+
+#if defined(_LANGUAGE_C) || defined(_LANGUAGE_C_PLUS_PLUS)
+
+/**************************************************************************
+ *
+ * Function prototypes
+ *
+ */
+
+/* Rumble PAK interface */
+
+extern s32		osMotorInit(OSMesgQueue *, OSPfs *, int);
+extern s32		osMotorStart(OSPfs *);
+extern s32		osMotorStop(OSPfs *);
+
+
+#endif  /* defined(_LANGUAGE_C) || defined(_LANGUAGE_C_PLUS_PLUS) */
+
+#ifdef _LANGUAGE_C_PLUS_PLUS
+}
+#endif
+
+#endif /* !_OS_MOTOR_H_ */
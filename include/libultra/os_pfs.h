@@ -0,0 +1,133 @@
+#ifndef _OS_PFS_H_
+#define	_OS_PFS_H_
+
+#ifdef _LANGUAGE_C_PLUS_PLUS
+extern "C" {
+#endif
+
+#include <libultra/ultratypes.h>
+#include <libultra/os_message.h>
+
+
+#if defined(_LANGUAGE_C) || defined(_LANGUAGE_C_PLUS_PLUS)
+
+/**************************************************************************
+ *
+ * Type definitions
+ *
+ */
+
+/*
+ * Structure for file system
+ */
+typedef struct {
+	int		status;
+	OSMesgQueue 	*queue;
+	int		channel;
+	u8		id[32];
+	u8		label[32];
+	int		version;
+	int		dir_size;
+	int		inode_table;		/* block location */
+	int		minode_table;		/* mirrioring inode_table */
+	int		dir_table;		/* block location */
+	int		inode_start_page;	/* page # */
+	u8		banks;
+	u8		activebank;
+} OSPfs;
+
+typedef struct {
+	u32	file_size;	/* bytes */
+  	u32 	game_code;
+  	u16 	company_code;
+  	char  	ext_name[4];
+  	char 	game_name[16];
+} OSPfsState;
+
+
+#endif /* defined(_LANGUAGE_C) || defined(_LANGUAGE_C_PLUS_PLUS) */
+
+/**************************************************************************
+ *
+ * Global definitions
+ *
+ */
+
+/* File System size */
+#define	OS_PFS_VERSION		0x0200
+#define	OS_PFS_VERSION_HI	(OS_PFS_VERSION >> 8)
+#define	OS_PFS_VERSION_LO	(OS_PFS_VERSION & 255)
+
+#define PFS_INODE_SIZE_PER_PAGE	128
+#define PFS_FILE_NAME_LEN       16
+#define PFS_FILE_EXT_LEN        4
+#define BLOCKSIZE		32		/* bytes */
+#define PFS_ONE_PAGE            8		/* blocks */
+#define PFS_MAX_BANKS		62
+
+/* File System flag */
+
+#define PFS_READ                0
+#define PFS_WRITE               1
+#define PFS_CREATE              2
+
+/* File System status */
+#define PFS_INITIALIZED		0x1
+#define PFS_CORRUPTED		0x2
+#define PFS_ID0_CORRUPTED	0x4
+#define PFS_MOTOR_INITIALIZED	0x8
+#define PFS_GBPAK_INITIALIZED	0x10
+
+/* File System error number */
+
+#define PFS_ERR_NOPACK		1	/* no memory card is plugged or   */
+#define PFS_ERR_NEW_PACK	2	/* ram pack has been changed to a */
+					/* different one 		  */
+#define PFS_ERR_INCONSISTENT	3	/* need to run Pfschecker*/
+#define PFS_ERR_CONTRFAIL	CONT_OVERRUN_ERROR
+#define PFS_ERR_INVALID		5	/* invalid parameter or file not exist*/
+#define PFS_ERR_BAD_DATA        6       /* the data read from pack are bad*/
+#define PFS_DATA_FULL		7	/* no free pages on ram pack*/
+#define PFS_DIR_FULL		8	/* no free directories on ram pack*/
+#define PFS_ERR_EXIST		9	/* file exists*/
+#define PFS_ERR_ID_FATAL	10	/* dead ram pack */
+#define PFS_ERR_DEVICE		11	/* wrong device type*/
+#define PFS_ERR_NO_GBCART	12	/* no gb cartridge (64GB-PAK) */
+#define PFS_ERR_NEW_GBCART	13	/* gb cartridge may be changed */
+
+
+#if defined(_LANGUAGE_C) || defined(_LANGUAGE_C_PLUS_PLUS)
+
+/**************************************************************************
+ *
+ * Function prototypes
+ *
+ */
+
+/* file system interface */
+
+extern s32 osPfsInitPak(OSMesgQueue *, OSPfs *, int);
+extern s32 osPfsRepairId(OSPfs *);
+extern s32 osPfsInit(OSMesgQueue *, OSPfs *, int);
+extern s32 osPfsReFormat(OSPfs *, OSMesgQueue *, int);
+extern s32 osPfsChecker(OSPfs *);
+extern s32 osPfsAllocateFile(OSPfs *, u16, u32, u8 *, u8 *, int, s32 *);
+extern s32 osPfsFindFile(OSPfs *, u16, u32, u8 *, u8 *, s32 *);
+extern s32 osPfsDeleteFile(OSPfs *, u16, u32, u8 *, u8 *);
+extern s32 osPfsReadWriteFile(OSPfs *, s32, u8, int, int, u8 *);
+extern s32 osPfsFileState(OSPfs *, s32, OSPfsState *);
+extern s32 osPfsGetLabel(OSPfs *, u8 *, int *);
+extern s32 osPfsSetLabel(OSPfs *, u8 *);
+extern s32 osPfsIsPlug(OSMesgQueue *, u8 *);
+extern s32 osPfsFreeBlocks(OSPfs *, s32 *);
+extern s32 osPfsNumFiles(OSPfs *, s32 *, s32 *);
+
+
+#endif /* defined(_LANGUAGE_C) || defined(_LANGUAGE_C_PLUS_PLUS) */
+
+
+#ifdef _LANGUAGE_C_PLUS_PLUS
+}
+#endif
+
+#endif /* !_OS_PFS_H_ */
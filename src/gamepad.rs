@@ -1,4 +1,8 @@
+pub mod bindings;
+pub mod controller_pak;
 pub mod manager;
+pub mod mapping;
 pub mod providers;
-mod types;
-mod utils;
+pub mod recording;
+pub mod types;
+pub mod utils;
@@ -0,0 +1,244 @@
+use crate::gamepad::types::{N64Button, OSControllerPad};
+use serde::{Deserialize, Serialize};
+
+/// Longest macro that can be recorded, in polls.
+pub const MAX_MACRO_FRAMES: usize = 600;
+
+/// The pad state played back for one poll of a macro.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MacroFrame {
+    pub buttons: u16,
+    pub stick_x: i8,
+    pub stick_y: i8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BindingMode {
+    /// Presses and releases the button every `half_period` polls while it's held,
+    /// e.g. 2 fires at 15 Hz when the game polls 60 times per second.
+    Turbo { half_period: u32 },
+    /// Each press latches the button down or releases it.
+    Toggle,
+    /// Each press plays back the frames, one per poll, in place of the button.
+    Macro { frames: Vec<MacroFrame> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ButtonBehavior {
+    pub button: N64Button,
+    pub mode: BindingMode,
+}
+
+/// Changes how the buttons of a port behave on their way to the game.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortBindings {
+    pub behaviors: Vec<ButtonBehavior>,
+}
+
+impl PortBindings {
+    pub fn get(&self, button: N64Button) -> Option<&BindingMode> {
+        self.behaviors
+            .iter()
+            .find(|behavior| behavior.button == button)
+            .map(|behavior| &behavior.mode)
+    }
+
+    /// Sets the behavior of the button, `None` restores the regular one.
+    pub fn set(&mut self, button: N64Button, mode: Option<BindingMode>) {
+        self.behaviors.retain(|behavior| behavior.button != button);
+        if let Some(mode) = mode {
+            self.behaviors.push(ButtonBehavior { button, mode });
+        }
+    }
+}
+
+struct MacroPlayback {
+    button: N64Button,
+    frame: usize,
+}
+
+/// The state of the bindings of one port, carried from one poll to the next.
+#[derive(Default)]
+pub struct BindingLayer {
+    held: u16,
+    toggled: u16,
+    /// The polls since each turbo button was pressed.
+    turbo_polls: Vec<(N64Button, u32)>,
+    playback: Option<MacroPlayback>,
+}
+
+impl BindingLayer {
+    /// Applies the bindings to the pad. `new_poll` is set on the first read after
+    /// `osContStartReadData`, which is when presses are picked up and macros advance,
+    /// further reads within the same poll see the same output.
+    pub fn apply(&mut self, bindings: &PortBindings, pad: &mut OSControllerPad, new_poll: bool) {
+        let held = pad.button;
+
+        if new_poll {
+            let pressed = held & !self.held;
+            self.held = held;
+            self.advance(bindings, pressed);
+        }
+
+        for behavior in &bindings.behaviors {
+            let mask = behavior.button as u16;
+            match &behavior.mode {
+                BindingMode::Turbo { half_period } => {
+                    let polls = self
+                        .turbo_polls
+                        .iter()
+                        .find(|(button, _)| *button == behavior.button);
+
+                    if let Some((_, polls)) = polls {
+                        // starts pressed, then alternates every half period
+                        if (polls / (*half_period).max(1)) % 2 == 1 {
+                            pad.button &= !mask;
+                        }
+                    }
+                }
+                BindingMode::Toggle => {
+                    pad.button = (pad.button & !mask) | (self.toggled & mask);
+                }
+                BindingMode::Macro { .. } => pad.button &= !mask,
+            }
+        }
+
+        if let Some(playback) = self.playback.as_ref() {
+            let frame = match bindings.get(playback.button) {
+                Some(BindingMode::Macro { frames }) => frames.get(playback.frame),
+                _ => None,
+            };
+
+            if let Some(frame) = frame {
+                pad.button |= frame.buttons;
+                if frame.stick_x != 0 {
+                    pad.stick_x = frame.stick_x;
+                }
+                if frame.stick_y != 0 {
+                    pad.stick_y = frame.stick_y;
+                }
+            }
+        }
+    }
+
+    fn advance(&mut self, bindings: &PortBindings, pressed: u16) {
+        let held = self.held;
+        self.turbo_polls
+            .retain(|(button, _)| held & *button as u16 != 0);
+        for (_, polls) in &mut self.turbo_polls {
+            *polls += 1;
+        }
+
+        if let Some(playback) = self.playback.as_mut() {
+            playback.frame += 1;
+        }
+
+        for behavior in &bindings.behaviors {
+            let mask = behavior.button as u16;
+            if pressed & mask == 0 {
+                continue;
+            }
+
+            match &behavior.mode {
+                BindingMode::Turbo { .. } => {
+                    self.turbo_polls.push((behavior.button, 0));
+                }
+                BindingMode::Toggle => self.toggled ^= mask,
+                BindingMode::Macro { .. } => {
+                    self.playback = Some(MacroPlayback {
+                        button: behavior.button,
+                        frame: 0,
+                    });
+                }
+            }
+        }
+
+        let finished = self.playback.as_ref().is_some_and(|playback| {
+            !matches!(
+                bindings.get(playback.button),
+                Some(BindingMode::Macro { frames }) if playback.frame < frames.len()
+            )
+        });
+        if finished {
+            self.playback = None;
+        }
+    }
+
+    /// Releases latched buttons and stops any macro.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poll(layer: &mut BindingLayer, bindings: &PortBindings, buttons: u16) -> OSControllerPad {
+        let mut pad = OSControllerPad {
+            button: buttons,
+            ..Default::default()
+        };
+        layer.apply(bindings, &mut pad, true);
+        pad
+    }
+
+    #[test]
+    fn test_turbo_and_toggle() {
+        let mut bindings = PortBindings::default();
+        bindings.set(N64Button::A, Some(BindingMode::Turbo { half_period: 2 }));
+        bindings.set(N64Button::Z, Some(BindingMode::Toggle));
+
+        let mut layer = BindingLayer::default();
+        let (a, z) = (N64Button::A as u16, N64Button::Z as u16);
+
+        // turbo is pressed for two polls and released for two polls
+        assert_eq!(poll(&mut layer, &bindings, a | z).button, a | z);
+        assert_eq!(poll(&mut layer, &bindings, a).button, a | z);
+        assert_eq!(poll(&mut layer, &bindings, a).button, z);
+
+        // reads within the same poll see the same output
+        let mut pad = OSControllerPad {
+            button: a,
+            ..Default::default()
+        };
+        layer.apply(&bindings, &mut pad, false);
+        assert_eq!(pad.button, z);
+
+        assert_eq!(poll(&mut layer, &bindings, a).button, z);
+        assert_eq!(poll(&mut layer, &bindings, a).button, a | z);
+        assert_eq!(poll(&mut layer, &bindings, 0).button, z);
+
+        // pressing again starts over, and pressing the toggled button releases it
+        assert_eq!(poll(&mut layer, &bindings, a | z).button, a);
+        assert_eq!(poll(&mut layer, &bindings, 0).button, 0);
+    }
+
+    #[test]
+    fn test_macro() {
+        let frame = |buttons, stick_x| MacroFrame {
+            buttons,
+            stick_x,
+            stick_y: 0,
+        };
+
+        let mut bindings = PortBindings::default();
+        bindings.set(
+            N64Button::L,
+            Some(BindingMode::Macro {
+                frames: vec![frame(N64Button::A as u16, 0), frame(0, 80)],
+            }),
+        );
+
+        let mut layer = BindingLayer::default();
+        let l = N64Button::L as u16;
+
+        let pad = poll(&mut layer, &bindings, l);
+        assert_eq!(pad.button, N64Button::A as u16);
+
+        let pad = poll(&mut layer, &bindings, l);
+        assert_eq!((pad.button, pad.stick_x), (0, 80));
+
+        assert_eq!(poll(&mut layer, &bindings, 0), OSControllerPad::default());
+    }
+}
@@ -0,0 +1,853 @@
+use crate::gamepad::manager::{GamepadManager, GAMEPAD_ERR_FAILED};
+use log::warn;
+use std::ffi::CStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Size of a controller pak image, 128 pages of 256 bytes.
+pub const PAK_SIZE: usize = 32 * 1024;
+
+const PAGE_SIZE: usize = 256;
+const PAGE_COUNT: usize = PAK_SIZE / PAGE_SIZE;
+const BLOCK_SIZE: usize = 32;
+
+const ID_PAGE: usize = 0;
+const INODE_PAGE: usize = 1;
+const INODE_BACKUP_PAGE: usize = 2;
+const NOTE_PAGE: usize = 3;
+const FIRST_DATA_PAGE: usize = 5;
+
+const ID_BLOCK_OFFSETS: [usize; 4] = [0x20, 0x60, 0x80, 0xC0];
+// PFS_LABEL_AREA, the last block of the id page
+const LABEL_OFFSET: usize = 7 * BLOCK_SIZE;
+const LABEL_SIZE: usize = 32;
+
+const NOTE_SIZE: usize = 32;
+pub const MAX_FILES: usize = 16;
+
+const INODE_LAST_PAGE: u16 = 0x0001;
+const INODE_FREE_PAGE: u16 = 0x0003;
+
+pub const PFS_FILE_NAME_LEN: usize = 16;
+pub const PFS_FILE_EXT_LEN: usize = 4;
+
+// MARK: - Errors
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PfsError {
+    NoPack,
+    Inconsistent,
+    Invalid,
+    DataFull,
+    DirFull,
+    Exist,
+    IdFatal,
+}
+
+impl PfsError {
+    /// Returns the matching `PFS_ERR_*` value from libultra.
+    pub fn code(&self) -> i32 {
+        match self {
+            PfsError::NoPack => 1,
+            PfsError::Inconsistent => 3,
+            PfsError::Invalid => 5,
+            PfsError::DataFull => 7,
+            PfsError::DirFull => 8,
+            PfsError::Exist => 9,
+            PfsError::IdFatal => 10,
+        }
+    }
+}
+
+// MARK: - Types
+
+/// Identifies a file (note) stored on the pak.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PfsFileName {
+    pub company_code: u16,
+    pub game_code: u32,
+    pub game_name: [u8; PFS_FILE_NAME_LEN],
+    pub ext_name: [u8; PFS_FILE_EXT_LEN],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OSPfsState {
+    pub file_size: u32,
+    pub game_code: u32,
+    pub company_code: u16,
+    pub ext_name: [u8; PFS_FILE_EXT_LEN],
+    pub game_name: [u8; PFS_FILE_NAME_LEN],
+}
+
+/// An emulated controller pak, laid out like the original so images
+/// can be exchanged with other tools (`.mpk`). Changes stay in memory until
+/// the pak is flushed.
+pub struct ControllerPak {
+    data: Vec<u8>,
+    path: Option<PathBuf>,
+    dirty: bool,
+}
+
+impl Default for ControllerPak {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ControllerPak {
+    /// Creates a formatted pak that only lives in memory.
+    pub fn new() -> Self {
+        let mut pak = Self {
+            data: vec![0; PAK_SIZE],
+            path: None,
+            dirty: false,
+        };
+
+        pak.format();
+        pak
+    }
+
+    /// Opens the pak image at the given path, creating a formatted one if it doesn't exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        if !path.exists() {
+            let mut pak = Self::new();
+            pak.path = Some(path);
+            pak.flush()?;
+            return Ok(pak);
+        }
+
+        let data = fs::read(&path)?;
+        if data.len() != PAK_SIZE {
+            anyhow::bail!(
+                "Controller pak image {} has an invalid size of {} bytes",
+                path.display(),
+                data.len()
+            );
+        }
+
+        Ok(Self {
+            data,
+            path: Some(path),
+            dirty: false,
+        })
+    }
+
+    /// Writes the image back to disk if it changed since the last flush, no-op for
+    /// in-memory paks. Runs on every poll, on `osPfsFreeBlocks`, when the pak is
+    /// removed and on shutdown.
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        if let (Some(path), true) = (&self.path, self.dirty) {
+            fs::write(path, &self.data)?;
+        }
+
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Whether the image has changes that weren't flushed yet.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn format(&mut self) {
+        self.data.fill(0);
+
+        // id blocks, repeated in four places for redundancy
+        let mut id = [0u8; BLOCK_SIZE];
+        id[0x18..0x1A].copy_from_slice(&0x0001u16.to_be_bytes()); // device id
+        id[0x1A] = 0x01; // banks
+        let checksum = id_checksum(&id);
+        id[0x1C..0x1E].copy_from_slice(&checksum.to_be_bytes());
+        id[0x1E..0x20].copy_from_slice(&(0xFFF2u16.wrapping_sub(checksum)).to_be_bytes());
+
+        for offset in ID_BLOCK_OFFSETS {
+            let start = ID_PAGE * PAGE_SIZE + offset;
+            self.data[start..start + BLOCK_SIZE].copy_from_slice(&id);
+        }
+
+        // inode table, every data page starts out free
+        for page in FIRST_DATA_PAGE..PAGE_COUNT {
+            self.set_inode(page, INODE_FREE_PAGE);
+        }
+
+        self.commit_inodes();
+        self.dirty = true;
+    }
+
+    /// Validates the pak, restoring the id and inode table from their backups if needed.
+    pub fn check(&mut self) -> Result<(), PfsError> {
+        self.repair_id()?;
+
+        if self.inode_checksum(INODE_PAGE) == self.data[INODE_PAGE * PAGE_SIZE + 1] {
+            return Ok(());
+        }
+
+        if self.inode_checksum(INODE_BACKUP_PAGE) != self.data[INODE_BACKUP_PAGE * PAGE_SIZE + 1] {
+            return Err(PfsError::Inconsistent);
+        }
+
+        let (primary, backup) = (INODE_PAGE * PAGE_SIZE, INODE_BACKUP_PAGE * PAGE_SIZE);
+        self.data.copy_within(backup..backup + PAGE_SIZE, primary);
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    /// Makes sure the main id block is valid, restoring it from one of its copies.
+    pub fn repair_id(&mut self) -> Result<(), PfsError> {
+        let valid = ID_BLOCK_OFFSETS.iter().copied().find(|offset| {
+            let block = &self.data[*offset..*offset + BLOCK_SIZE];
+            let checksum = id_checksum(block);
+            let stored = u16::from_be_bytes([block[0x1C], block[0x1D]]);
+            let inverted = u16::from_be_bytes([block[0x1E], block[0x1F]]);
+            checksum == stored && 0xFFF2u16.wrapping_sub(checksum) == inverted
+        });
+
+        match valid {
+            Some(offset) if offset == ID_BLOCK_OFFSETS[0] => Ok(()),
+            Some(offset) => {
+                let main = ID_BLOCK_OFFSETS[0];
+                self.data.copy_within(offset..offset + BLOCK_SIZE, main);
+                self.dirty = true;
+                Ok(())
+            }
+            None => Err(PfsError::IdFatal),
+        }
+    }
+
+    pub fn label(&self) -> &[u8] {
+        &self.data[LABEL_OFFSET..LABEL_OFFSET + LABEL_SIZE]
+    }
+
+    pub fn set_label(&mut self, label: &[u8]) {
+        let len = label.len().min(LABEL_SIZE);
+        let area = &mut self.data[LABEL_OFFSET..LABEL_OFFSET + LABEL_SIZE];
+        area.fill(0);
+        area[..len].copy_from_slice(&label[..len]);
+        self.dirty = true;
+    }
+
+    // File Methods
+
+    pub fn find_file(&self, name: &PfsFileName) -> Result<usize, PfsError> {
+        (0..MAX_FILES)
+            .find(|file_no| self.note(*file_no).as_ref() == Some(name))
+            .ok_or(PfsError::Invalid)
+    }
+
+    pub fn allocate_file(&mut self, name: &PfsFileName, size: usize) -> Result<usize, PfsError> {
+        if size == 0 || name.company_code == 0 || name.game_code == 0 {
+            return Err(PfsError::Invalid);
+        }
+
+        if self.find_file(name).is_ok() {
+            return Err(PfsError::Exist);
+        }
+
+        let file_no = (0..MAX_FILES)
+            .find(|file_no| self.note(*file_no).is_none())
+            .ok_or(PfsError::DirFull)?;
+
+        let pages_needed = size.div_ceil(PAGE_SIZE);
+        let free_pages: Vec<usize> = (FIRST_DATA_PAGE..PAGE_COUNT)
+            .filter(|page| self.inode(*page) == INODE_FREE_PAGE)
+            .take(pages_needed)
+            .collect();
+
+        if free_pages.len() < pages_needed {
+            return Err(PfsError::DataFull);
+        }
+
+        // chain the pages together, the last one marks the end of the file
+        for (i, page) in free_pages.iter().enumerate() {
+            let next = free_pages
+                .get(i + 1)
+                .map_or(INODE_LAST_PAGE, |next| *next as u16);
+            self.set_inode(*page, next);
+            self.page_mut(*page).fill(0);
+        }
+
+        self.commit_inodes();
+        self.set_note(file_no, Some((name, free_pages[0] as u16)));
+        self.dirty = true;
+
+        Ok(file_no)
+    }
+
+    pub fn delete_file(&mut self, name: &PfsFileName) -> Result<(), PfsError> {
+        let file_no = self.find_file(name)?;
+
+        for page in self.file_pages(file_no)? {
+            self.set_inode(page, INODE_FREE_PAGE);
+        }
+
+        self.commit_inodes();
+        self.set_note(file_no, None);
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    pub fn file_state(&self, file_no: usize) -> Result<OSPfsState, PfsError> {
+        let name = self.note(file_no).ok_or(PfsError::Invalid)?;
+        let pages = self.file_pages(file_no)?;
+
+        Ok(OSPfsState {
+            file_size: (pages.len() * PAGE_SIZE) as u32,
+            game_code: name.game_code,
+            company_code: name.company_code,
+            ext_name: name.ext_name,
+            game_name: name.game_name,
+        })
+    }
+
+    pub fn read_file(
+        &self,
+        file_no: usize,
+        offset: usize,
+        buffer: &mut [u8],
+    ) -> Result<(), PfsError> {
+        let pages = self.file_range(file_no, offset, buffer.len())?;
+
+        for (i, chunk) in buffer.chunks_mut(BLOCK_SIZE).enumerate() {
+            let position = offset + i * BLOCK_SIZE;
+            let start = pages[position / PAGE_SIZE] * PAGE_SIZE + position % PAGE_SIZE;
+            chunk.copy_from_slice(&self.data[start..start + chunk.len()]);
+        }
+
+        Ok(())
+    }
+
+    pub fn write_file(
+        &mut self,
+        file_no: usize,
+        offset: usize,
+        data: &[u8],
+    ) -> Result<(), PfsError> {
+        let pages = self.file_range(file_no, offset, data.len())?;
+
+        for (i, chunk) in data.chunks(BLOCK_SIZE).enumerate() {
+            let position = offset + i * BLOCK_SIZE;
+            let start = pages[position / PAGE_SIZE] * PAGE_SIZE + position % PAGE_SIZE;
+            self.data[start..start + chunk.len()].copy_from_slice(chunk);
+        }
+
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn free_bytes(&self) -> usize {
+        (FIRST_DATA_PAGE..PAGE_COUNT)
+            .filter(|page| self.inode(*page) == INODE_FREE_PAGE)
+            .count()
+            * PAGE_SIZE
+    }
+
+    /// Returns the maximum number of files and the number of files in use.
+    pub fn num_files(&self) -> (usize, usize) {
+        let used = (0..MAX_FILES)
+            .filter(|file_no| self.note(*file_no).is_some())
+            .count();
+
+        (MAX_FILES, used)
+    }
+
+    // Helpers
+
+    fn page_mut(&mut self, page: usize) -> &mut [u8] {
+        &mut self.data[page * PAGE_SIZE..(page + 1) * PAGE_SIZE]
+    }
+
+    fn inode(&self, page: usize) -> u16 {
+        let offset = INODE_PAGE * PAGE_SIZE + page * 2;
+        u16::from_be_bytes([self.data[offset], self.data[offset + 1]])
+    }
+
+    fn set_inode(&mut self, page: usize, value: u16) {
+        let offset = INODE_PAGE * PAGE_SIZE + page * 2;
+        self.data[offset..offset + 2].copy_from_slice(&value.to_be_bytes());
+    }
+
+    fn inode_checksum(&self, inode_page: usize) -> u8 {
+        let start = inode_page * PAGE_SIZE + FIRST_DATA_PAGE * 2;
+        let end = (inode_page + 1) * PAGE_SIZE;
+        self.data[start..end]
+            .iter()
+            .fold(0u8, |acc, byte| acc.wrapping_add(*byte))
+    }
+
+    /// Updates the inode checksum and mirrors the table to its backup page.
+    fn commit_inodes(&mut self) {
+        self.data[INODE_PAGE * PAGE_SIZE + 1] = self.inode_checksum(INODE_PAGE);
+
+        let (primary, backup) = (INODE_PAGE * PAGE_SIZE, INODE_BACKUP_PAGE * PAGE_SIZE);
+        self.data.copy_within(primary..primary + PAGE_SIZE, backup);
+    }
+
+    fn note_offset(file_no: usize) -> usize {
+        NOTE_PAGE * PAGE_SIZE + file_no * NOTE_SIZE
+    }
+
+    fn note(&self, file_no: usize) -> Option<PfsFileName> {
+        if file_no >= MAX_FILES {
+            return None;
+        }
+
+        let note = &self.data[Self::note_offset(file_no)..][..NOTE_SIZE];
+        let game_code = u32::from_be_bytes([note[0], note[1], note[2], note[3]]);
+        let company_code = u16::from_be_bytes([note[4], note[5]]);
+        if game_code == 0 || company_code == 0 {
+            return None;
+        }
+
+        let mut name = PfsFileName {
+            company_code,
+            game_code,
+            game_name: [0; PFS_FILE_NAME_LEN],
+            ext_name: [0; PFS_FILE_EXT_LEN],
+        };
+        name.ext_name.copy_from_slice(&note[12..16]);
+        name.game_name.copy_from_slice(&note[16..32]);
+
+        Some(name)
+    }
+
+    fn set_note(&mut self, file_no: usize, entry: Option<(&PfsFileName, u16)>) {
+        let offset = Self::note_offset(file_no);
+        let note = &mut self.data[offset..offset + NOTE_SIZE];
+        note.fill(0);
+
+        if let Some((name, start_page)) = entry {
+            note[0..4].copy_from_slice(&name.game_code.to_be_bytes());
+            note[4..6].copy_from_slice(&name.company_code.to_be_bytes());
+            note[6..8].copy_from_slice(&start_page.to_be_bytes());
+            note[8] = 0x02; // status: occupied
+            note[12..16].copy_from_slice(&name.ext_name);
+            note[16..32].copy_from_slice(&name.game_name);
+        }
+    }
+
+    fn file_pages(&self, file_no: usize) -> Result<Vec<usize>, PfsError> {
+        if self.note(file_no).is_none() {
+            return Err(PfsError::Invalid);
+        }
+
+        let offset = Self::note_offset(file_no) + 6;
+        let mut page = u16::from_be_bytes([self.data[offset], self.data[offset + 1]]) as usize;
+        let mut pages = Vec::new();
+
+        loop {
+            if !(FIRST_DATA_PAGE..PAGE_COUNT).contains(&page) || pages.len() >= PAGE_COUNT {
+                return Err(PfsError::Inconsistent);
+            }
+
+            pages.push(page);
+
+            match self.inode(page) {
+                INODE_LAST_PAGE => return Ok(pages),
+                next => page = next as usize,
+            }
+        }
+    }
+
+    /// Validates an access to a file, returning the pages backing it.
+    fn file_range(
+        &self,
+        file_no: usize,
+        offset: usize,
+        size: usize,
+    ) -> Result<Vec<usize>, PfsError> {
+        if !offset.is_multiple_of(BLOCK_SIZE) || !size.is_multiple_of(BLOCK_SIZE) {
+            return Err(PfsError::Invalid);
+        }
+
+        let pages = self.file_pages(file_no)?;
+        if offset + size > pages.len() * PAGE_SIZE {
+            return Err(PfsError::Invalid);
+        }
+
+        Ok(pages)
+    }
+}
+
+fn id_checksum(block: &[u8]) -> u16 {
+    block[..0x1C].chunks(2).fold(0u16, |acc, word| {
+        acc.wrapping_add(u16::from_be_bytes([word[0], word[1]]))
+    })
+}
+
+// MARK: - C API
+
+unsafe fn file_name(
+    company_code: u16,
+    game_code: u32,
+    game_name: *const u8,
+    ext_name: *const u8,
+) -> PfsFileName {
+    let mut name = PfsFileName {
+        company_code,
+        game_code,
+        game_name: [0; PFS_FILE_NAME_LEN],
+        ext_name: [0; PFS_FILE_EXT_LEN],
+    };
+
+    name.game_name
+        .copy_from_slice(std::slice::from_raw_parts(game_name, PFS_FILE_NAME_LEN));
+    name.ext_name
+        .copy_from_slice(std::slice::from_raw_parts(ext_name, PFS_FILE_EXT_LEN));
+
+    name
+}
+
+fn with_pak<F>(manager: Option<&mut GamepadManager>, channel: i32, f: F) -> i32
+where
+    F: FnOnce(&mut ControllerPak) -> Result<(), PfsError>,
+{
+    let manager = manager.unwrap();
+
+    match manager.controller_pak_mut(channel as usize) {
+        Some(pak) => match f(pak) {
+            Ok(()) => 0,
+            Err(e) => e.code(),
+        },
+        None => PfsError::NoPack.code(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn GamepadManagerInsertControllerPak(
+    manager: Option<&mut GamepadManager>,
+    channel: i32,
+    path_raw: *const i8,
+) -> i32 {
+    let manager = manager.unwrap();
+    let path_str: &CStr = CStr::from_ptr(path_raw);
+    let path = std::str::from_utf8(path_str.to_bytes()).unwrap();
+
+    match manager.insert_controller_pak(channel as usize, path) {
+        Ok(()) => 0,
+        Err(e) => manager.report_error(
+            GAMEPAD_ERR_FAILED,
+            format!("Error inserting controller pak: {e}"),
+        ),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerRemoveControllerPak(
+    manager: Option<&mut GamepadManager>,
+    channel: i32,
+) {
+    let manager = manager.unwrap();
+    manager.remove_controller_pak(channel as usize);
+}
+
+#[no_mangle]
+pub extern "C" fn ControllerPakIsPlugged(manager: Option<&mut GamepadManager>) -> u8 {
+    let manager = manager.unwrap();
+
+    (0..crate::gamepad::types::MAXCONTROLLERS)
+        .filter(|slot| manager.controller_pak_mut(*slot).is_some())
+        .fold(0, |acc, slot| acc | (1 << slot))
+}
+
+#[no_mangle]
+pub extern "C" fn ControllerPakInit(manager: Option<&mut GamepadManager>, channel: i32) -> i32 {
+    with_pak(manager, channel, |pak| pak.check())
+}
+
+#[no_mangle]
+pub extern "C" fn ControllerPakRepairId(manager: Option<&mut GamepadManager>, channel: i32) -> i32 {
+    with_pak(manager, channel, |pak| pak.repair_id())
+}
+
+#[no_mangle]
+pub extern "C" fn ControllerPakFormat(manager: Option<&mut GamepadManager>, channel: i32) -> i32 {
+    with_pak(manager, channel, |pak| {
+        pak.format();
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ControllerPakAllocateFile(
+    manager: Option<&mut GamepadManager>,
+    channel: i32,
+    company_code: u16,
+    game_code: u32,
+    game_name: *const u8,
+    ext_name: *const u8,
+    size: i32,
+    file_no: Option<&mut i32>,
+) -> i32 {
+    let name = file_name(company_code, game_code, game_name, ext_name);
+    let file_no = file_no.unwrap();
+
+    with_pak(manager, channel, |pak| {
+        if size <= 0 {
+            return Err(PfsError::Invalid);
+        }
+
+        *file_no = pak.allocate_file(&name, size as usize)? as i32;
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ControllerPakFindFile(
+    manager: Option<&mut GamepadManager>,
+    channel: i32,
+    company_code: u16,
+    game_code: u32,
+    game_name: *const u8,
+    ext_name: *const u8,
+    file_no: Option<&mut i32>,
+) -> i32 {
+    let name = file_name(company_code, game_code, game_name, ext_name);
+    let file_no = file_no.unwrap();
+
+    with_pak(manager, channel, |pak| {
+        *file_no = pak.find_file(&name)? as i32;
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ControllerPakDeleteFile(
+    manager: Option<&mut GamepadManager>,
+    channel: i32,
+    company_code: u16,
+    game_code: u32,
+    game_name: *const u8,
+    ext_name: *const u8,
+) -> i32 {
+    let name = file_name(company_code, game_code, game_name, ext_name);
+    with_pak(manager, channel, |pak| pak.delete_file(&name))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ControllerPakReadWriteFile(
+    manager: Option<&mut GamepadManager>,
+    channel: i32,
+    file_no: i32,
+    write: bool,
+    offset: i32,
+    size: i32,
+    buffer: *mut u8,
+) -> i32 {
+    if buffer.is_null() || file_no < 0 || offset < 0 || size < 0 {
+        return PfsError::Invalid.code();
+    }
+
+    let buffer = std::slice::from_raw_parts_mut(buffer, size as usize);
+    with_pak(manager, channel, |pak| {
+        if write {
+            pak.write_file(file_no as usize, offset as usize, buffer)
+        } else {
+            pak.read_file(file_no as usize, offset as usize, buffer)
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn ControllerPakFileState(
+    manager: Option<&mut GamepadManager>,
+    channel: i32,
+    file_no: i32,
+    state: Option<&mut OSPfsState>,
+) -> i32 {
+    let state = state.unwrap();
+
+    with_pak(manager, channel, |pak| {
+        if file_no < 0 {
+            return Err(PfsError::Invalid);
+        }
+
+        *state = pak.file_state(file_no as usize)?;
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn ControllerPakFreeBlocks(
+    manager: Option<&mut GamepadManager>,
+    channel: i32,
+    bytes_not_used: Option<&mut i32>,
+) -> i32 {
+    let bytes_not_used = bytes_not_used.unwrap();
+
+    with_pak(manager, channel, |pak| {
+        // games query the free space after saving, a good time to write the image back
+        if let Err(e) = pak.flush() {
+            warn!("Error writing controller pak: {e}");
+        }
+
+        *bytes_not_used = pak.free_bytes() as i32;
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn ControllerPakNumFiles(
+    manager: Option<&mut GamepadManager>,
+    channel: i32,
+    max_files: Option<&mut i32>,
+    files_used: Option<&mut i32>,
+) -> i32 {
+    let max_files = max_files.unwrap();
+    let files_used = files_used.unwrap();
+
+    with_pak(manager, channel, |pak| {
+        let (max, used) = pak.num_files();
+        *max_files = max as i32;
+        *files_used = used as i32;
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ControllerPakGetLabel(
+    manager: Option<&mut GamepadManager>,
+    channel: i32,
+    label: *mut u8,
+    len: Option<&mut i32>,
+) -> i32 {
+    let len = len.unwrap();
+
+    with_pak(manager, channel, |pak| {
+        let data = pak.label();
+        std::ptr::copy_nonoverlapping(data.as_ptr(), label, data.len());
+        *len = data
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(data.len()) as i32;
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ControllerPakSetLabel(
+    manager: Option<&mut GamepadManager>,
+    channel: i32,
+    label: *const u8,
+) -> i32 {
+    let label = std::slice::from_raw_parts(label, LABEL_SIZE);
+    with_pak(manager, channel, |pak| {
+        pak.set_label(label);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_name(ext: u8) -> PfsFileName {
+        PfsFileName {
+            company_code: 0x3031,
+            game_code: 0x4E534D45,
+            game_name: *b"HELIX TEST\0\0\0\0\0\0",
+            ext_name: [ext, 0, 0, 0],
+        }
+    }
+
+    #[test]
+    fn test_allocate_read_write_delete() {
+        let mut pak = ControllerPak::new();
+        assert_eq!(pak.check(), Ok(()));
+        assert_eq!(pak.free_bytes(), 123 * PAGE_SIZE);
+
+        let file_no = pak.allocate_file(&test_name(b'A'), 600).unwrap();
+        assert_eq!(pak.free_bytes(), 120 * PAGE_SIZE);
+        assert_eq!(pak.file_state(file_no).unwrap().file_size, 768);
+        assert_eq!(
+            pak.allocate_file(&test_name(b'A'), 32),
+            Err(PfsError::Exist)
+        );
+
+        let data: Vec<u8> = (0..512).map(|i| i as u8).collect();
+        pak.write_file(file_no, 256, &data).unwrap();
+
+        let mut buffer = vec![0; 512];
+        pak.read_file(file_no, 256, &mut buffer).unwrap();
+        assert_eq!(buffer, data);
+        assert_eq!(
+            pak.read_file(file_no, 512, &mut buffer),
+            Err(PfsError::Invalid)
+        );
+
+        assert_eq!(pak.num_files(), (MAX_FILES, 1));
+        pak.delete_file(&test_name(b'A')).unwrap();
+        assert_eq!(pak.num_files(), (MAX_FILES, 0));
+        assert_eq!(pak.free_bytes(), 123 * PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_flush_only_when_dirty() {
+        let path = std::env::temp_dir().join(format!("helix-pak-{}.mpk", std::process::id()));
+        let mut pak = ControllerPak::open(&path).unwrap();
+        assert!(!pak.is_dirty());
+        let formatted = fs::read(&path).unwrap();
+
+        // changes stay in memory until the pak is flushed
+        let file_no = pak.allocate_file(&test_name(b'C'), 256).unwrap();
+        pak.write_file(file_no, 0, &[0xAA; 32]).unwrap();
+        assert!(pak.is_dirty());
+        assert_eq!(fs::read(&path).unwrap(), formatted);
+
+        pak.flush().unwrap();
+        assert!(!pak.is_dirty());
+        assert_eq!(fs::read(&path).unwrap(), pak.data());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_label_area() {
+        let mut pak = ControllerPak::new();
+        pak.set_label(b"HELIX");
+
+        // where libultra keeps it, clear of the id blocks
+        assert_eq!(&pak.data()[0xE0..0xE5], b"HELIX");
+        assert_eq!(&pak.label()[..6], b"HELIX\0");
+        assert_eq!(pak.check(), Ok(()));
+    }
+
+    #[test]
+    fn test_read_write_null_buffer() {
+        let mut manager = GamepadManager::with_providers(Vec::new());
+        for write in [false, true] {
+            let code = unsafe {
+                ControllerPakReadWriteFile(
+                    Some(&mut manager),
+                    0,
+                    0,
+                    write,
+                    0,
+                    0,
+                    std::ptr::null_mut(),
+                )
+            };
+            assert_eq!(code, PfsError::Invalid.code());
+        }
+    }
+
+    #[test]
+    fn test_check_restores_backups() {
+        let mut pak = ControllerPak::new();
+        pak.allocate_file(&test_name(b'B'), 256).unwrap();
+
+        let before = pak.data().to_vec();
+        pak.data[ID_BLOCK_OFFSETS[0]] ^= 0xFF;
+        pak.data[INODE_PAGE * PAGE_SIZE + 20] ^= 0xFF;
+
+        assert_eq!(pak.check(), Ok(()));
+        assert_eq!(pak.data(), &before[..]);
+    }
+}
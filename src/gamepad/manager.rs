@@ -1,16 +1,76 @@
-use winit::event::{KeyboardInput, ModifiersState};
+use winit::event::{DeviceEvent, WindowEvent};
 
+use super::bindings::{BindingLayer, BindingMode, MacroFrame, PortBindings, MAX_MACRO_FRAMES};
+use super::controller_pak::ControllerPak;
+use super::mapping::{KeyboardMapping, MappingProfile, MouseLook, MouseLookTarget, N64Input};
 use super::providers::gilrs::GirlsGamepadProvider;
-use super::types::{GamepadBits, OSControllerPad};
+use super::recording::{InputPlayback, InputRecorder};
+use super::types::{
+    GamepadBits, N64Button, OSContStatus, OSControllerPad, CONT_CARD_ON, CONT_NO_RESPONSE_ERROR,
+    MAXCONTROLLERS, PFS_ERR_DEVICE, PFS_ERR_NOPACK,
+};
+use super::utils::StickSettings;
 use crate::gamepad::providers::keyboard::KeyboardGamepadProvider;
-use crate::gamepad::providers::{Gamepad, GamepadProvider};
+use crate::gamepad::providers::mouse::{MouseGamepadProvider, MouseSettings};
+use crate::gamepad::providers::virtual_gamepad::{
+    VirtualGamepadProvider, VirtualGamepads, VirtualPadState,
+};
+use crate::gamepad::providers::{Gamepad, GamepadProvider, GamepadService, HotplugEvent};
 
-use std::ptr::null_mut;
+use log::{info, warn};
+use std::collections::VecDeque;
+use std::ffi::{CStr, CString};
+use std::path::{Path, PathBuf};
+
+pub type Pads = [OSControllerPad; MAXCONTROLLERS];
+pub type Statuses = [OSContStatus; MAXCONTROLLERS];
+
+/// SDL GameController mappings loaded when the manager is created, if the file exists.
+pub const CONTROLLER_DATABASE_FILE: &str = "gamecontrollerdb.txt";
+
+/// Returned by the C API when an operation fails, see `GamepadManagerGetLastError` for the reason.
+pub const GAMEPAD_ERR_FAILED: i32 = -1;
+/// A device backend couldn't be initialized, the manager runs with the remaining providers.
+/// Only reported by `GamepadManagerGetBackendStatus`, `osContInit` still succeeds.
+pub const GAMEPAD_ERR_BACKEND_UNAVAILABLE: i32 = -2;
+pub const GAMEPAD_ERR_INVALID_ARGUMENT: i32 = -3;
+
+// events are dropped oldest first when nobody polls them
+const MAX_PENDING_CONTROLLER_EVENTS: usize = 32;
+
+/// Reports a controller being plugged into or unplugged from a port.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControllerEvent {
+    pub slot: u8,
+    pub connected: bool,
+}
 
 pub struct GamepadManager {
     gamepads: Vec<Gamepad>,
-    gamepad_bits: GamepadBits,
     providers: Vec<Box<dyn GamepadProvider>>,
+    mapping_profile: MappingProfile,
+    device_profiles: Vec<MappingProfile>,
+    keyboard_mapping: KeyboardMapping,
+    stick_settings: [StickSettings; MAXCONTROLLERS],
+    rumble_strength: f32,
+    controller_paks: [Option<ControllerPak>; MAXCONTROLLERS],
+    recorder: Option<InputRecorder>,
+    playback: Option<InputPlayback>,
+    playback_frame: Option<Pads>,
+    poll_pending: bool,
+    virtual_gamepads: VirtualGamepads,
+    reserved_slots: [Option<[u8; 16]>; MAXCONTROLLERS],
+    controller_events: VecDeque<ControllerEvent>,
+    controller_event_callback: Option<Box<dyn Fn(ControllerEvent)>>,
+    last_pads: Pads,
+    pending_binding: Option<N64Input>,
+    mapping_profile_path: Option<PathBuf>,
+    mouse_settings: MouseSettings,
+    binding_layers: [BindingLayer; MAXCONTROLLERS],
+    macro_recording: Option<(usize, Vec<MacroFrame>)>,
+    provider_errors: Vec<String>,
+    last_error: Option<CString>,
 }
 
 impl Default for GamepadManager {
@@ -20,106 +80,1574 @@ impl Default for GamepadManager {
 }
 
 impl GamepadManager {
+    /// Creates a manager reading from every provider available on the platform,
+    /// the ones that fail to start are listed by `provider_errors`.
     pub fn new() -> Self {
+        let mut providers: Vec<Box<dyn GamepadProvider>> = Vec::new();
+        let mut provider_errors = Vec::new();
+
+        match GirlsGamepadProvider::new() {
+            Ok(provider) => providers.push(Box::new(provider)),
+            Err(e) => {
+                warn!("Gamepads are unavailable, continuing without them: {e}");
+                provider_errors.push(format!("Gamepads are unavailable: {e}"));
+            }
+        }
+
+        providers.push(Box::new(KeyboardGamepadProvider::new()));
+        providers.push(Box::new(MouseGamepadProvider::new()));
+
+        let mut manager = Self::with_providers(providers);
+        manager.provider_errors = provider_errors;
+
+        if Path::new(CONTROLLER_DATABASE_FILE).exists() {
+            if let Err(e) = manager.load_controller_database(CONTROLLER_DATABASE_FILE) {
+                warn!("Error loading controller database: {e}");
+            }
+        }
+
+        manager
+    }
+
+    /// Creates a manager reading from the given providers, virtual gamepads
+    /// are always available on top of them.
+    pub fn with_providers(mut providers: Vec<Box<dyn GamepadProvider>>) -> Self {
+        let virtual_gamepads = VirtualGamepads::new();
+        providers.push(Box::new(VirtualGamepadProvider::new(
+            virtual_gamepads.clone(),
+        )));
+
         Self {
             gamepads: Vec::new(),
-            gamepad_bits: null_mut(),
-            providers: vec![
-                Box::new(GirlsGamepadProvider::new()),
-                Box::new(KeyboardGamepadProvider::new()),
-            ],
+            providers,
+            mapping_profile: MappingProfile::default(),
+            device_profiles: Vec::new(),
+            keyboard_mapping: KeyboardMapping::default(),
+            stick_settings: [StickSettings::default(); MAXCONTROLLERS],
+            rumble_strength: 1.0,
+            controller_paks: Default::default(),
+            recorder: None,
+            playback: None,
+            playback_frame: None,
+            poll_pending: false,
+            virtual_gamepads,
+            reserved_slots: [None; MAXCONTROLLERS],
+            controller_events: VecDeque::new(),
+            controller_event_callback: None,
+            last_pads: Pads::default(),
+            pending_binding: None,
+            mapping_profile_path: None,
+            mouse_settings: MouseSettings::default(),
+            binding_layers: Default::default(),
+            macro_recording: None,
+            provider_errors: Vec::new(),
+            last_error: None,
         }
     }
 
-    pub unsafe fn init(&mut self, gamepad_bits: GamepadBits) {
-        *gamepad_bits = 0;
+    /// Why the providers that failed to start are missing.
+    pub fn provider_errors(&self) -> &[String] {
+        &self.provider_errors
+    }
+
+    /// The message of the last error reported through the C API.
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error
+            .as_ref()
+            .and_then(|error| error.to_str().ok())
+    }
+
+    fn set_last_error(&mut self, message: String) {
+        // a C string can't hold nul bytes
+        self.last_error = CString::new(message.replace('\0', "")).ok();
+    }
+
+    /// Logs the error and keeps it as the last error, returning `code`.
+    pub(crate) fn report_error(&mut self, code: i32, message: String) -> i32 {
+        warn!("{message}");
+        self.set_last_error(message);
+        code
+    }
 
-        self.gamepad_bits = gamepad_bits;
+    /// Scans for controllers and returns the bits of the occupied ports.
+    pub fn init(&mut self) -> u8 {
         self.scan_for_controllers();
+        self.gamepad_bits()
     }
 
+    /// Called on every `osContStartReadData`. Devices only come and go through the
+    /// providers' hotplug events, full scans are left to `init`, `reset` and mapping
+    /// changes, so a poll doesn't allocate once the pads are connected (see
+    /// `benches/gamepad_poll.rs`).
     pub fn process_events(&mut self) {
-        // TODO: Perhaps update controllers at a slower rate than the game loop?
-        self.scan_for_controllers();
+        self.update_controllers();
+        self.update_binding();
+
+        for provider in &mut self.providers {
+            provider.begin_poll();
+        }
+
+        // saves reach the disk at most one poll after the game made them
+        self.flush_controller_paks();
+
+        // every call marks a new poll, recordings are made and replayed, turbo
+        // alternates and macros advance at this cadence
+        self.poll_pending = true;
+    }
+
+    /// Fills `pads` with the state of every port.
+    pub fn read(&mut self, pads: &mut Pads) {
+        let new_poll = std::mem::take(&mut self.poll_pending);
+
+        // frames are only recorded on a new poll, so playback only advances on one
+        if new_poll {
+            self.advance_playback();
+        }
+
+        match self.playback_frame {
+            // recordings already went through the bindings
+            Some(frame) => *pads = frame,
+            None => {
+                self.read_providers(pads);
+                self.apply_bindings(pads, new_poll);
+            }
+        }
+
+        self.last_pads = *pads;
+
+        if !new_poll {
+            return;
+        }
+
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(e) = recorder.record_frame(pads) {
+                warn!("Error recording input, stopping recording: {e}");
+                self.recorder = None;
+            }
+        }
+
+        if let Some((slot, frames)) = self.macro_recording.as_mut() {
+            if frames.len() < MAX_MACRO_FRAMES {
+                let pad = &pads[*slot];
+                frames.push(MacroFrame {
+                    buttons: pad.button,
+                    stick_x: pad.stick_x,
+                    stick_y: pad.stick_y,
+                });
+            }
+        }
+    }
 
-        for provider in self.providers.iter_mut() {
-            provider.process_events();
+    fn advance_playback(&mut self) {
+        if let Some(playback) = self.playback.as_mut() {
+            self.playback_frame = playback.next_frame().copied();
+            if self.playback_frame.is_none() {
+                info!(
+                    "Input playback finished after {} frames",
+                    playback.position()
+                );
+                self.playback = None;
+            }
         }
     }
 
-    pub unsafe fn read(&mut self, pad: *mut OSControllerPad) {
-        // TODO: Handle current slot?
+    fn apply_bindings(&mut self, pads: &mut Pads, new_poll: bool) {
+        let ports = &self.mapping_profile.ports;
+        for (slot, pad) in pads.iter_mut().enumerate() {
+            if pad.errno == 0 {
+                self.binding_layers[slot].apply(&ports[slot], pad, new_poll);
+            }
+        }
+    }
 
-        (*pad).button = 0;
-        (*pad).stick_x = 0;
-        (*pad).stick_y = 0;
-        (*pad).errno = 0;
+    fn read_providers(&self, pads: &mut Pads) {
+        for (slot, pad) in pads.iter_mut().enumerate() {
+            *pad = OSControllerPad::default();
+            if !self.is_slot_occupied(slot) {
+                pad.errno = CONT_NO_RESPONSE_ERROR;
+            }
+        }
 
         for controller in &self.gamepads {
-            for provider in &self.providers {
-                provider.read(controller, pad);
+            let input = self.providers[controller.provider].read(controller);
+            input.apply(&mut pads[controller.slot as usize]);
+        }
+    }
+
+    /// Fills `statuses` with the status of every port.
+    pub fn query(&self, statuses: &mut Statuses) {
+        for (slot, status) in statuses.iter_mut().enumerate() {
+            match self.primary_gamepad(slot) {
+                Some(gamepad) => {
+                    status.type_ = gamepad.controller_type();
+                    status.status = if self.controller_pak(slot).is_some() {
+                        CONT_CARD_ON
+                    } else {
+                        0
+                    };
+                    status.errno = 0;
+                }
+                None => {
+                    status.type_ = 0;
+                    status.status = 0;
+                    status.errno = CONT_NO_RESPONSE_ERROR;
+                }
             }
         }
     }
 
+    /// Picks up the controllers that were connected or disconnected since the last poll.
+    pub fn start_query(&mut self) {
+        self.update_controllers();
+    }
+
+    /// Stops any running rumble and rescans the connected controllers.
+    pub fn reset(&mut self) {
+        for slot in 0..MAXCONTROLLERS {
+            self.set_rumble(slot, false);
+            self.binding_layers[slot].reset();
+        }
+
+        self.scan_for_controllers();
+    }
+
+    pub fn is_slot_occupied(&self, slot: usize) -> bool {
+        self.gamepads
+            .iter()
+            .any(|gamepad| gamepad.slot as usize == slot)
+    }
+
+    /// The devices currently assigned to a port.
+    pub fn gamepads(&self) -> &[Gamepad] {
+        &self.gamepads
+    }
+
+    /// The pads handed to the game on the last read.
+    pub fn last_pads(&self) -> &Pads {
+        &self.last_pads
+    }
+
+    /// The bits of the ports that currently have a controller.
+    pub fn gamepad_bits(&self) -> u8 {
+        self.gamepads
+            .iter()
+            .fold(0, |acc, gamepad| acc | (1 << gamepad.slot))
+    }
+
     fn scan_for_controllers(&mut self) {
         self.gamepads.clear();
 
-        for provider in &self.providers {
-            for device in provider.scan() {
-                self.gamepads.push(device);
+        let mut devices = Vec::new();
+        for (index, provider) in self.providers.iter_mut().enumerate() {
+            for mut device in provider.scan() {
+                device.provider = index;
+                devices.push(device);
+            }
+        }
+
+        // devices bound to a port claim it before physical devices fill the remaining ones
+        devices.sort_by_key(|device| {
+            !matches!(
+                device.service,
+                GamepadService::Virtual(_) | GamepadService::Mouse(_)
+            )
+        });
+        for device in devices {
+            self.add_controller(device);
+        }
+    }
+
+    fn update_controllers(&mut self) {
+        for index in 0..self.providers.len() {
+            for event in self.providers[index].process_events() {
+                match event {
+                    HotplugEvent::Connected(mut device) => {
+                        device.provider = index;
+                        if let Some(slot) = self.add_controller(device) {
+                            self.push_controller_event(ControllerEvent {
+                                slot,
+                                connected: true,
+                            });
+                        }
+                    }
+                    HotplugEvent::Disconnected(service) => {
+                        if let Some(slot) = self.remove_controller(service) {
+                            self.push_controller_event(ControllerEvent {
+                                slot,
+                                connected: false,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Adds the device to the first port available to it, returning the port.
+    fn add_controller(&mut self, mut device: Gamepad) -> Option<u8> {
+        if self
+            .gamepads
+            .iter()
+            .any(|gamepad| gamepad.service == device.service)
+        {
+            return None;
+        }
+
+        // virtual gamepads and the mouse sit in the port they were given, physical devices
+        // take back the port they had before disconnecting or else the first free
+        // port, and the keyboard always shares the first port so it can be used
+        // alongside a gamepad
+        let slot = match device.service {
+            GamepadService::Keyboard() => 0,
+            GamepadService::Virtual(slot) | GamepadService::Mouse(slot) => slot as usize,
+            _ => {
+                let free = |slot: &usize| !self.is_slot_taken(*slot);
+                let reserved = (0..MAXCONTROLLERS)
+                    .filter(free)
+                    .find(|&slot| self.reserved_slots[slot] == Some(device.uuid));
+                let unreserved = (0..MAXCONTROLLERS)
+                    .filter(free)
+                    .find(|&slot| self.reserved_slots[slot].is_none());
+
+                reserved
+                    .or(unreserved)
+                    .or_else(|| (0..MAXCONTROLLERS).find(free))?
+            }
+        };
+
+        if let GamepadService::GilRs(_) = device.service {
+            self.reserved_slots[slot] = None;
+        }
+
+        info!("{} connected to port {}", device.name, slot + 1);
+
+        device.slot = slot as u8;
+        device.stick_settings = self.stick_settings[slot];
+        self.gamepads.push(device);
+        Some(slot as u8)
+    }
+
+    /// Removes the device, keeping its port reserved in case it comes back.
+    fn remove_controller(&mut self, service: GamepadService) -> Option<u8> {
+        let index = self
+            .gamepads
+            .iter()
+            .position(|gamepad| gamepad.service == service)?;
+        let device = self.gamepads.remove(index);
+
+        if let GamepadService::GilRs(_) = device.service {
+            self.reserved_slots[device.slot as usize] = Some(device.uuid);
+        }
+
+        info!("{} disconnected from port {}", device.name, device.slot + 1);
+        Some(device.slot)
+    }
+
+    /// Whether the port is used by a device other than the keyboard.
+    fn is_slot_taken(&self, slot: usize) -> bool {
+        self.gamepads.iter().any(|gamepad| {
+            gamepad.slot as usize == slot && !matches!(gamepad.service, GamepadService::Keyboard())
+        })
+    }
+
+    // Controller Event Methods
+
+    fn push_controller_event(&mut self, event: ControllerEvent) {
+        if let Some(callback) = self.controller_event_callback.as_ref() {
+            callback(event);
+        }
+
+        if self.controller_events.len() == MAX_PENDING_CONTROLLER_EVENTS {
+            self.controller_events.pop_front();
+        }
+        self.controller_events.push_back(event);
+    }
+
+    /// Returns the oldest controller event that hasn't been polled yet.
+    pub fn poll_controller_event(&mut self) -> Option<ControllerEvent> {
+        self.controller_events.pop_front()
+    }
+
+    /// Sets a callback invoked whenever a controller is connected or disconnected.
+    pub fn set_controller_event_callback<F: Fn(ControllerEvent) + 'static>(&mut self, callback: F) {
+        self.controller_event_callback = Some(Box::new(callback));
+    }
+
+    pub fn clear_controller_event_callback(&mut self) {
+        self.controller_event_callback = None;
+    }
+
+    /// The name of the device that identifies the port.
+    pub fn controller_name(&self, slot: usize) -> Option<&str> {
+        self.primary_gamepad(slot)
+            .map(|gamepad| gamepad.name.as_str())
+    }
+
+    /// The device that identifies the port, a mouse takes precedence over
+    /// gamepads, which take precedence over the keyboard.
+    fn primary_gamepad(&self, slot: usize) -> Option<&Gamepad> {
+        self.gamepads
+            .iter()
+            .filter(|gamepad| gamepad.slot as usize == slot)
+            .min_by_key(|gamepad| match gamepad.service {
+                GamepadService::Mouse(_) => 0,
+                GamepadService::Keyboard() => 2,
+                _ => 1,
+            })
+    }
+
+    // Stick Settings Methods
+
+    /// The stick settings of the port, the defaults for an invalid port.
+    pub fn stick_settings(&self, slot: usize) -> StickSettings {
+        self.stick_settings.get(slot).copied().unwrap_or_default()
+    }
+
+    pub fn set_stick_settings(&mut self, slot: usize, settings: StickSettings) {
+        if slot >= MAXCONTROLLERS {
+            warn!("Ignoring stick settings for invalid controller port {slot}");
+            return;
+        }
+
+        self.stick_settings[slot] = settings;
+
+        for gamepad in self.gamepads.iter_mut() {
+            if gamepad.slot as usize == slot {
+                gamepad.stick_settings = settings;
+            }
+        }
+    }
+
+    // Rumble Methods
+
+    pub fn supports_rumble(&self, slot: usize) -> bool {
+        self.gamepads
+            .iter()
+            .filter(|gamepad| gamepad.slot as usize == slot)
+            .any(|gamepad| {
+                self.providers
+                    .iter()
+                    .any(|provider| provider.supports_rumble(gamepad))
+            })
+    }
+
+    pub fn set_rumble(&mut self, slot: usize, enabled: bool) {
+        let strength = if enabled { self.rumble_strength } else { 0.0 };
+
+        for gamepad in &self.gamepads {
+            if gamepad.slot as usize != slot {
+                continue;
+            }
+
+            for provider in &mut self.providers {
+                provider.set_rumble(gamepad, strength);
+            }
+        }
+    }
+
+    pub fn rumble_strength(&self) -> f32 {
+        self.rumble_strength
+    }
+
+    pub fn set_rumble_strength(&mut self, strength: f32) {
+        self.rumble_strength = strength.clamp(0.0, 1.0);
+    }
+
+    // Controller Pak Methods
+
+    /// Inserts the controller pak image stored at the given path into the port,
+    /// creating a freshly formatted one if it doesn't exist yet.
+    pub fn insert_controller_pak<P: AsRef<Path>>(
+        &mut self,
+        slot: usize,
+        path: P,
+    ) -> anyhow::Result<()> {
+        if slot >= MAXCONTROLLERS {
+            anyhow::bail!("Invalid controller port {slot}");
+        }
+
+        let pak = ControllerPak::open(path)?;
+        self.remove_controller_pak(slot);
+        self.controller_paks[slot] = Some(pak);
+        Ok(())
+    }
+
+    pub fn remove_controller_pak(&mut self, slot: usize) {
+        if let Some(mut pak) = self.controller_paks.get_mut(slot).and_then(Option::take) {
+            if let Err(e) = pak.flush() {
+                warn!("Error writing controller pak: {e}");
+            }
+        }
+    }
+
+    /// Writes back the controller paks that changed since they were last flushed.
+    pub fn flush_controller_paks(&mut self) {
+        for pak in self.controller_paks.iter_mut().flatten() {
+            if pak.is_dirty() {
+                if let Err(e) = pak.flush() {
+                    warn!("Error writing controller pak: {e}");
+                }
             }
         }
+    }
+
+    pub fn controller_pak(&self, slot: usize) -> Option<&ControllerPak> {
+        self.controller_paks.get(slot).and_then(Option::as_ref)
+    }
 
-        unsafe {
-            *self.gamepad_bits = if !self.gamepads.is_empty() { 1 } else { 0 };
+    pub fn controller_pak_mut(&mut self, slot: usize) -> Option<&mut ControllerPak> {
+        self.controller_paks.get_mut(slot).and_then(Option::as_mut)
+    }
+
+    // Recording Methods
+
+    /// Starts writing the pads of every poll to the given path.
+    pub fn start_recording<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        game_id: &str,
+    ) -> anyhow::Result<()> {
+        self.stop_recording()?;
+        self.recorder = Some(InputRecorder::create(path, game_id)?);
+        Ok(())
+    }
+
+    pub fn stop_recording(&mut self) -> anyhow::Result<()> {
+        if let Some(recorder) = self.recorder.take() {
+            info!(
+                "Input recording finished after {} frames",
+                recorder.frame_count()
+            );
+            recorder.finish()?;
         }
+
+        Ok(())
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
     }
 
-    // Keyboard Handlind Methods (from Window)
-    pub fn handle_keyboard_input(&mut self, input: KeyboardInput) {
+    /// Replays the recording at the given path in place of the live providers.
+    pub fn start_playback<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<()> {
+        let playback = InputPlayback::open(path)?;
+        let header = playback.header();
+        info!(
+            "Playing back {} frames recorded for {} with Helix {}",
+            playback.frame_count(),
+            header.game_id,
+            header.helix_version
+        );
+
+        self.playback = Some(playback);
+        self.playback_frame = None;
+        Ok(())
+    }
+
+    pub fn stop_playback(&mut self) {
+        self.playback = None;
+        self.playback_frame = None;
+    }
+
+    pub fn is_playing_back(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    // Virtual Gamepad Methods
+
+    /// A handle to drive the virtual gamepads from outside the manager.
+    pub fn virtual_gamepads(&self) -> VirtualGamepads {
+        self.virtual_gamepads.clone()
+    }
+
+    pub fn connect_virtual_gamepad(&mut self, slot: usize) {
+        self.virtual_gamepads.connect(slot);
+        self.update_controllers();
+    }
+
+    pub fn disconnect_virtual_gamepad(&mut self, slot: usize) {
+        self.virtual_gamepads.disconnect(slot);
+        self.update_controllers();
+    }
+
+    pub fn set_virtual_gamepad_state(&mut self, slot: usize, state: VirtualPadState) {
+        self.virtual_gamepads.set_state(slot, state);
+    }
+
+    // Mapping Profile Methods
+
+    pub fn mapping_profile(&self) -> &MappingProfile {
+        &self.mapping_profile
+    }
+
+    /// The path the mapping profile was last loaded from or saved to.
+    pub fn mapping_profile_path(&self) -> Option<&Path> {
+        self.mapping_profile_path.as_deref()
+    }
+
+    pub fn set_mapping_profile(&mut self, profile: MappingProfile) {
         for provider in &mut self.providers {
-            provider.handle_keyboard_input(input);
+            provider.set_mapping_profile(&profile);
         }
+
+        self.mapping_profile = profile;
+    }
+
+    pub fn load_mapping_profile<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<()> {
+        let profile = MappingProfile::load(&path)?;
+        self.set_mapping_profile(profile);
+        self.mapping_profile_path = Some(path.as_ref().to_path_buf());
+        Ok(())
     }
 
-    pub fn handle_modifiers_changed(&mut self, modifiers: ModifiersState) {
+    pub fn save_mapping_profile<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<()> {
+        self.mapping_profile.save(&path)?;
+        self.mapping_profile_path = Some(path.as_ref().to_path_buf());
+        Ok(())
+    }
+
+    pub fn device_profiles(&self) -> &[MappingProfile] {
+        &self.device_profiles
+    }
+
+    /// Sets the profiles picked automatically for the devices they list,
+    /// other devices keep using the active mapping profile.
+    pub fn set_device_profiles(&mut self, profiles: Vec<MappingProfile>) {
         for provider in &mut self.providers {
-            provider.handle_modifiers_changed(modifiers);
+            provider.set_device_profiles(&profiles);
         }
+
+        self.device_profiles = profiles;
+        self.scan_for_controllers();
     }
-}
 
-// MARK: - C API
+    /// Loads every profile stored in the directory as device profiles, returning how many were loaded.
+    pub fn load_device_profiles<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<usize> {
+        let profiles = MappingProfile::load_dir(path)?;
+        let count = profiles.len();
+        self.set_device_profiles(profiles);
+        Ok(count)
+    }
 
-#[no_mangle]
-pub extern "C" fn GamepadManagerCreate() -> Box<GamepadManager> {
-    let hub = GamepadManager::new();
-    Box::new(hub)
-}
+    /// Adds the SDL GameController mappings stored in a `gamecontrollerdb.txt` file,
+    /// they take precedence over the ones bundled with gilrs.
+    pub fn load_controller_database<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<()> {
+        let mappings = std::fs::read_to_string(path)?;
+        for provider in &mut self.providers {
+            provider.add_controller_mappings(&mappings)?;
+        }
 
-#[no_mangle]
-pub unsafe extern "C" fn GamepadManagerInit(
-    manager: Option<&mut GamepadManager>,
-    gamepad_bits: GamepadBits,
-) -> i32 {
-    let manager = manager.unwrap();
-    manager.init(gamepad_bits);
+        let count = mappings
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .count();
+        info!("Loaded {count} controller mappings");
 
-    0
-}
+        self.scan_for_controllers();
+        Ok(())
+    }
 
-#[no_mangle]
-pub extern "C" fn GamepadManagerProcessEvents(manager: Option<&mut GamepadManager>) {
-    let manager = manager.unwrap();
-    manager.process_events();
-}
+    // Button Behavior Methods
 
-#[no_mangle]
-pub unsafe extern "C" fn GamepadManagerGetReadData(
-    manager: Option<&mut GamepadManager>,
-    pad: *mut OSControllerPad,
-) {
-    let manager = manager.unwrap();
-    manager.read(pad);
+    pub fn port_bindings(&self, slot: usize) -> Option<&PortBindings> {
+        self.mapping_profile.ports.get(slot)
+    }
+
+    /// Sets the turbo, toggle or macro behavior of a button of the port,
+    /// it's saved along with the mapping profile.
+    pub fn set_button_behavior(
+        &mut self,
+        slot: usize,
+        button: N64Button,
+        mode: Option<BindingMode>,
+    ) {
+        if slot >= MAXCONTROLLERS {
+            warn!("Ignoring button behavior for invalid controller port {slot}");
+            return;
+        }
+
+        let mut profile = self.mapping_profile.clone();
+        profile.ports[slot].set(button, mode);
+        self.set_mapping_profile(profile);
+        self.binding_layers[slot].reset();
+    }
+
+    /// Starts recording the output of the port, one frame per poll.
+    pub fn start_macro_recording(&mut self, slot: usize) {
+        if slot >= MAXCONTROLLERS {
+            warn!("Ignoring macro recording for invalid controller port {slot}");
+            return;
+        }
+
+        self.macro_recording = Some((slot, Vec::new()));
+    }
+
+    pub fn is_recording_macro(&self) -> bool {
+        self.macro_recording.is_some()
+    }
+
+    /// Stops the macro recording and binds it to the button, returning the
+    /// number of frames recorded.
+    pub fn finish_macro_recording(&mut self, button: N64Button) -> usize {
+        let Some((slot, mut frames)) = self.macro_recording.take() else {
+            return 0;
+        };
+
+        // drop the polls before the first input and after the last one
+        let idle = |frame: &MacroFrame| *frame == MacroFrame::default();
+        let end = frames
+            .iter()
+            .rposition(|frame| !idle(frame))
+            .map_or(0, |end| end + 1);
+        frames.truncate(end);
+        let start = frames.iter().position(|frame| !idle(frame)).unwrap_or(0);
+        frames.drain(..start);
+
+        let count = frames.len();
+        self.set_button_behavior(slot, button, Some(BindingMode::Macro { frames }));
+        count
+    }
+
+    pub fn cancel_macro_recording(&mut self) {
+        self.macro_recording = None;
+    }
+
+    /// Binds the next gamepad button pressed to the input.
+    pub fn start_binding(&mut self, input: N64Input) {
+        // forget presses that happened before the binding started
+        for provider in &mut self.providers {
+            provider.take_pressed_button();
+        }
+
+        self.pending_binding = Some(input);
+    }
+
+    pub fn cancel_binding(&mut self) {
+        self.pending_binding = None;
+    }
+
+    /// The input waiting for a button press to be bound to.
+    pub fn pending_binding(&self) -> Option<N64Input> {
+        self.pending_binding
+    }
+
+    fn update_binding(&mut self) {
+        let Some(input) = self.pending_binding else {
+            return;
+        };
+
+        let pressed = self
+            .providers
+            .iter_mut()
+            .find_map(|provider| provider.take_pressed_button());
+
+        if let Some(button) = pressed {
+            let mut profile = self.mapping_profile.clone();
+            profile.bind_button(button, input);
+            self.set_mapping_profile(profile);
+            self.pending_binding = None;
+        }
+    }
+
+    pub fn keyboard_mapping(&self) -> &KeyboardMapping {
+        &self.keyboard_mapping
+    }
+
+    pub fn set_keyboard_mapping(&mut self, mapping: KeyboardMapping) {
+        for provider in &mut self.providers {
+            provider.set_keyboard_mapping(&mapping);
+        }
+
+        self.keyboard_mapping = mapping;
+    }
+
+    pub fn load_keyboard_mapping<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<()> {
+        let mapping = KeyboardMapping::load(path)?;
+        self.set_keyboard_mapping(mapping);
+        Ok(())
+    }
+
+    pub fn save_keyboard_mapping<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        self.keyboard_mapping.save(path)
+    }
+
+    // Mouse Methods
+
+    pub fn mouse_settings(&self) -> MouseSettings {
+        self.mouse_settings
+    }
+
+    pub fn set_mouse_settings(&mut self, settings: MouseSettings) {
+        for provider in &mut self.providers {
+            provider.set_mouse_settings(&settings);
+        }
+
+        self.mouse_settings = settings;
+        self.update_controllers();
+    }
+
+    /// Whether the window should currently capture the pointer.
+    pub fn wants_pointer_capture(&self) -> bool {
+        self.providers
+            .iter()
+            .any(|provider| provider.wants_pointer_capture())
+    }
+
+    // Window Event Handling Methods
+
+    pub fn handle_window_event(&mut self, event: &WindowEvent) {
+        for provider in &mut self.providers {
+            provider.handle_window_event(event);
+        }
+    }
+
+    pub fn handle_device_event(&mut self, event: &DeviceEvent) {
+        for provider in &mut self.providers {
+            provider.handle_device_event(event);
+        }
+    }
+
+    // Shutdown Methods
+
+    /// Stops the rumble, finishes the input recording and writes the controller
+    /// paks back to disk. It runs when the manager is dropped.
+    pub fn shutdown(&mut self) {
+        for slot in 0..MAXCONTROLLERS {
+            self.set_rumble(slot, false);
+            self.remove_controller_pak(slot);
+        }
+
+        if let Err(e) = self.stop_recording() {
+            warn!("Error finishing input recording: {e}");
+        }
+    }
+}
+
+impl Drop for GamepadManager {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+// MARK: - C API
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerCreate() -> Box<GamepadManager> {
+    let hub = GamepadManager::new();
+    Box::new(hub)
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerFree(manager: Option<Box<GamepadManager>>) {
+    if let Some(manager) = manager {
+        drop(manager);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn GamepadManagerInit(
+    manager: Option<&mut GamepadManager>,
+    gamepad_bits: GamepadBits,
+) -> i32 {
+    let manager = manager.unwrap();
+    *gamepad_bits = manager.init();
+
+    // the remaining providers still work, games treat a nonzero result as no
+    // controllers so the failures are only reported through GamepadManagerGetBackendStatus
+    if !manager.provider_errors.is_empty() {
+        manager.set_last_error(manager.provider_errors.join(", "));
+    }
+
+    0
+}
+
+/// Returns `GAMEPAD_ERR_BACKEND_UNAVAILABLE` when a provider failed to start, 0 otherwise.
+#[no_mangle]
+pub extern "C" fn GamepadManagerGetBackendStatus(manager: Option<&mut GamepadManager>) -> i32 {
+    let manager = manager.unwrap();
+    if manager.provider_errors.is_empty() {
+        0
+    } else {
+        GAMEPAD_ERR_BACKEND_UNAVAILABLE
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerProcessEvents(manager: Option<&mut GamepadManager>) {
+    let manager = manager.unwrap();
+    manager.process_events();
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn GamepadManagerGetReadData(
+    manager: Option<&mut GamepadManager>,
+    pads: *mut OSControllerPad,
+) {
+    let manager = manager.unwrap();
+    manager.read(&mut *(pads as *mut Pads));
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerStartQuery(manager: Option<&mut GamepadManager>) {
+    let manager = manager.unwrap();
+    manager.start_query();
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn GamepadManagerGetQuery(
+    manager: Option<&mut GamepadManager>,
+    statuses: *mut OSContStatus,
+) {
+    let manager = manager.unwrap();
+    manager.query(&mut *(statuses as *mut Statuses));
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerReset(manager: Option<&mut GamepadManager>) {
+    let manager = manager.unwrap();
+    manager.reset();
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn GamepadManagerLoadMappingProfile(
+    manager: Option<&mut GamepadManager>,
+    path_raw: *const i8,
+) -> i32 {
+    let manager = manager.unwrap();
+    let path_str: &CStr = CStr::from_ptr(path_raw);
+    let path = std::str::from_utf8(path_str.to_bytes()).unwrap();
+
+    match manager.load_mapping_profile(path) {
+        Ok(()) => 0,
+        Err(e) => manager.report_error(
+            GAMEPAD_ERR_FAILED,
+            format!("Error loading mapping profile: {e}"),
+        ),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn GamepadManagerSaveMappingProfile(
+    manager: Option<&mut GamepadManager>,
+    path_raw: *const i8,
+) -> i32 {
+    let manager = manager.unwrap();
+    let path_str: &CStr = CStr::from_ptr(path_raw);
+    let path = std::str::from_utf8(path_str.to_bytes()).unwrap();
+
+    match manager.save_mapping_profile(path) {
+        Ok(()) => 0,
+        Err(e) => manager.report_error(
+            GAMEPAD_ERR_FAILED,
+            format!("Error saving mapping profile: {e}"),
+        ),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn GamepadManagerLoadKeyboardMapping(
+    manager: Option<&mut GamepadManager>,
+    path_raw: *const i8,
+) -> i32 {
+    let manager = manager.unwrap();
+    let path_str: &CStr = CStr::from_ptr(path_raw);
+    let path = std::str::from_utf8(path_str.to_bytes()).unwrap();
+
+    match manager.load_keyboard_mapping(path) {
+        Ok(()) => 0,
+        Err(e) => manager.report_error(
+            GAMEPAD_ERR_FAILED,
+            format!("Error loading keyboard mapping: {e}"),
+        ),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn GamepadManagerSaveKeyboardMapping(
+    manager: Option<&mut GamepadManager>,
+    path_raw: *const i8,
+) -> i32 {
+    let manager = manager.unwrap();
+    let path_str: &CStr = CStr::from_ptr(path_raw);
+    let path = std::str::from_utf8(path_str.to_bytes()).unwrap();
+
+    match manager.save_keyboard_mapping(path) {
+        Ok(()) => 0,
+        Err(e) => manager.report_error(
+            GAMEPAD_ERR_FAILED,
+            format!("Error saving keyboard mapping: {e}"),
+        ),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerGetStickSettings(
+    manager: Option<&mut GamepadManager>,
+    slot: u8,
+) -> StickSettings {
+    let manager = manager.unwrap();
+    manager.stick_settings(slot as usize)
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerSetStickSettings(
+    manager: Option<&mut GamepadManager>,
+    slot: u8,
+    settings: StickSettings,
+) {
+    let manager = manager.unwrap();
+    manager.set_stick_settings(slot as usize, settings);
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerMotorInit(manager: Option<&mut GamepadManager>, slot: i32) -> i32 {
+    let manager = manager.unwrap();
+    let slot = slot as usize;
+
+    if !manager.is_slot_occupied(slot) {
+        return PFS_ERR_NOPACK;
+    }
+
+    if !manager.supports_rumble(slot) {
+        return PFS_ERR_DEVICE;
+    }
+
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerMotorSet(
+    manager: Option<&mut GamepadManager>,
+    slot: i32,
+    enabled: bool,
+) -> i32 {
+    let manager = manager.unwrap();
+    let slot = slot as usize;
+
+    if !manager.is_slot_occupied(slot) {
+        return PFS_ERR_NOPACK;
+    }
+
+    manager.set_rumble(slot, enabled);
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerSetRumbleStrength(
+    manager: Option<&mut GamepadManager>,
+    strength: f32,
+) {
+    let manager = manager.unwrap();
+    manager.set_rumble_strength(strength);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn GamepadManagerStartRecording(
+    manager: Option<&mut GamepadManager>,
+    path_raw: *const i8,
+    game_id_raw: *const i8,
+) -> i32 {
+    let manager = manager.unwrap();
+    let path_str: &CStr = CStr::from_ptr(path_raw);
+    let path = std::str::from_utf8(path_str.to_bytes()).unwrap();
+    let game_id_str: &CStr = CStr::from_ptr(game_id_raw);
+    let game_id = std::str::from_utf8(game_id_str.to_bytes()).unwrap();
+
+    match manager.start_recording(path, game_id) {
+        Ok(()) => 0,
+        Err(e) => manager.report_error(
+            GAMEPAD_ERR_FAILED,
+            format!("Error starting input recording: {e}"),
+        ),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerStopRecording(manager: Option<&mut GamepadManager>) -> i32 {
+    let manager = manager.unwrap();
+
+    match manager.stop_recording() {
+        Ok(()) => 0,
+        Err(e) => manager.report_error(
+            GAMEPAD_ERR_FAILED,
+            format!("Error finishing input recording: {e}"),
+        ),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn GamepadManagerStartPlayback(
+    manager: Option<&mut GamepadManager>,
+    path_raw: *const i8,
+) -> i32 {
+    let manager = manager.unwrap();
+    let path_str: &CStr = CStr::from_ptr(path_raw);
+    let path = std::str::from_utf8(path_str.to_bytes()).unwrap();
+
+    match manager.start_playback(path) {
+        Ok(()) => 0,
+        Err(e) => manager.report_error(
+            GAMEPAD_ERR_FAILED,
+            format!("Error starting input playback: {e}"),
+        ),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerStopPlayback(manager: Option<&mut GamepadManager>) {
+    let manager = manager.unwrap();
+    manager.stop_playback();
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerIsPlayingBack(manager: Option<&mut GamepadManager>) -> bool {
+    let manager = manager.unwrap();
+    manager.is_playing_back()
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerConnectVirtualGamepad(
+    manager: Option<&mut GamepadManager>,
+    slot: u8,
+) {
+    let manager = manager.unwrap();
+    manager.connect_virtual_gamepad(slot as usize);
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerDisconnectVirtualGamepad(
+    manager: Option<&mut GamepadManager>,
+    slot: u8,
+) {
+    let manager = manager.unwrap();
+    manager.disconnect_virtual_gamepad(slot as usize);
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerSetVirtualGamepadState(
+    manager: Option<&mut GamepadManager>,
+    slot: u8,
+    buttons: u16,
+    stick_x: i8,
+    stick_y: i8,
+) {
+    let manager = manager.unwrap();
+    manager.set_virtual_gamepad_state(
+        slot as usize,
+        VirtualPadState {
+            buttons,
+            stick_x,
+            stick_y,
+        },
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerSetMouseSlot(manager: Option<&mut GamepadManager>, slot: i32) {
+    let manager = manager.unwrap();
+
+    let mut settings = manager.mouse_settings();
+    settings.slot = u8::try_from(slot).ok();
+    manager.set_mouse_settings(settings);
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerSetMouseSensitivity(
+    manager: Option<&mut GamepadManager>,
+    sensitivity: f32,
+) {
+    let manager = manager.unwrap();
+
+    let mut settings = manager.mouse_settings();
+    settings.sensitivity = sensitivity;
+    manager.set_mouse_settings(settings);
+}
+
+/// Enables mouse-look for the keyboard, `mode` is 0 to disable it,
+/// 1 to drive the stick and 2 to drive the C buttons.
+#[no_mangle]
+pub extern "C" fn GamepadManagerSetMouseLook(
+    manager: Option<&mut GamepadManager>,
+    mode: i32,
+    sensitivity: f32,
+    smoothing: f32,
+    decay: f32,
+) {
+    let manager = manager.unwrap();
+
+    let target = match mode {
+        1 => Some(MouseLookTarget::Stick),
+        2 => Some(MouseLookTarget::CButtons),
+        _ => None,
+    };
+
+    let mut mapping = manager.keyboard_mapping().clone();
+    mapping.mouse_look = target.map(|target| MouseLook {
+        target,
+        sensitivity,
+        smoothing: smoothing.clamp(0.0, 0.99),
+        decay: decay.clamp(0.0, 1.0),
+        ..mapping.mouse_look.unwrap_or_default()
+    });
+    manager.set_keyboard_mapping(mapping);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn GamepadManagerLoadControllerDatabase(
+    manager: Option<&mut GamepadManager>,
+    path_raw: *const i8,
+) -> i32 {
+    let manager = manager.unwrap();
+    let path_str: &CStr = CStr::from_ptr(path_raw);
+    let path = std::str::from_utf8(path_str.to_bytes()).unwrap();
+
+    match manager.load_controller_database(path) {
+        Ok(()) => 0,
+        Err(e) => manager.report_error(
+            GAMEPAD_ERR_FAILED,
+            format!("Error loading controller database: {e}"),
+        ),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn GamepadManagerLoadDeviceProfiles(
+    manager: Option<&mut GamepadManager>,
+    path_raw: *const i8,
+) -> i32 {
+    let manager = manager.unwrap();
+    let path_str: &CStr = CStr::from_ptr(path_raw);
+    let path = std::str::from_utf8(path_str.to_bytes()).unwrap();
+
+    match manager.load_device_profiles(path) {
+        Ok(count) => count as i32,
+        Err(e) => manager.report_error(
+            GAMEPAD_ERR_FAILED,
+            format!("Error loading device profiles: {e}"),
+        ),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerSetTurbo(
+    manager: Option<&mut GamepadManager>,
+    slot: u8,
+    button: u16,
+    half_period: u32,
+) -> i32 {
+    let manager = manager.unwrap();
+    if slot as usize >= MAXCONTROLLERS {
+        let message = format!("Invalid turbo port {slot}");
+        return manager.report_error(GAMEPAD_ERR_INVALID_ARGUMENT, message);
+    }
+
+    let Some(button) = N64Button::from_mask(button) else {
+        let message = format!("Invalid turbo button {button:#x}");
+        return manager.report_error(GAMEPAD_ERR_INVALID_ARGUMENT, message);
+    };
+
+    let mode = (half_period > 0).then_some(BindingMode::Turbo { half_period });
+    manager.set_button_behavior(slot as usize, button, mode);
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerSetToggle(
+    manager: Option<&mut GamepadManager>,
+    slot: u8,
+    button: u16,
+    enabled: bool,
+) -> i32 {
+    let manager = manager.unwrap();
+    if slot as usize >= MAXCONTROLLERS {
+        let message = format!("Invalid toggle port {slot}");
+        return manager.report_error(GAMEPAD_ERR_INVALID_ARGUMENT, message);
+    }
+
+    let Some(button) = N64Button::from_mask(button) else {
+        let message = format!("Invalid toggle button {button:#x}");
+        return manager.report_error(GAMEPAD_ERR_INVALID_ARGUMENT, message);
+    };
+
+    let mode = enabled.then_some(BindingMode::Toggle);
+    manager.set_button_behavior(slot as usize, button, mode);
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerStartMacroRecording(
+    manager: Option<&mut GamepadManager>,
+    slot: u8,
+) {
+    let manager = manager.unwrap();
+    manager.start_macro_recording(slot as usize);
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerFinishMacroRecording(
+    manager: Option<&mut GamepadManager>,
+    button: u16,
+) -> i32 {
+    let manager = manager.unwrap();
+    let Some(button) = N64Button::from_mask(button) else {
+        manager.cancel_macro_recording();
+        let message = format!("Invalid macro button {button:#x}");
+        return manager.report_error(GAMEPAD_ERR_INVALID_ARGUMENT, message);
+    };
+
+    manager.finish_macro_recording(button) as i32
+}
+
+/// Returns the message of the last error, or null if nothing failed yet. The string
+/// stays valid until the next error.
+#[no_mangle]
+pub extern "C" fn GamepadManagerGetLastError(manager: Option<&mut GamepadManager>) -> *const i8 {
+    let manager = manager.unwrap();
+    manager
+        .last_error
+        .as_ref()
+        .map_or(std::ptr::null(), |error| error.as_ptr())
+}
+
+type OnControllerEvent = unsafe extern "C" fn(event: ControllerEvent);
+
+#[no_mangle]
+pub unsafe extern "C" fn GamepadManagerPollControllerEvent(
+    manager: Option<&mut GamepadManager>,
+    event: *mut ControllerEvent,
+) -> bool {
+    let manager = manager.unwrap();
+
+    match manager.poll_controller_event() {
+        Some(next) => {
+            *event = next;
+            true
+        }
+        None => false,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn GamepadManagerSetControllerEventCallback(
+    manager: Option<&mut GamepadManager>,
+    callback: Option<OnControllerEvent>,
+) {
+    let manager = manager.unwrap();
+
+    match callback {
+        Some(callback) => manager.set_controller_event_callback(move |event| unsafe {
+            callback(event);
+        }),
+        None => manager.clear_controller_event_callback(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamepad::types::N64Button;
+
+    fn read_pads(manager: &mut GamepadManager) -> Pads {
+        let mut pads = Pads::default();
+        manager.read(&mut pads);
+        pads
+    }
+
+    #[test]
+    fn test_virtual_gamepads() {
+        let mut manager = GamepadManager::with_providers(Vec::new());
+        assert_eq!(manager.init(), 0);
+
+        manager.connect_virtual_gamepad(2);
+        assert_eq!(manager.gamepad_bits(), 0b100);
+
+        let pads = read_pads(&mut manager);
+        assert_eq!(pads[0].errno, CONT_NO_RESPONSE_ERROR);
+        assert_eq!(pads[2], OSControllerPad::default());
+
+        let handle = manager.virtual_gamepads();
+        handle.press(2, N64Button::A);
+        handle.press(2, N64Button::Start);
+        handle.set_stick(2, -40, 80);
+
+        let pads = read_pads(&mut manager);
+        assert_eq!(
+            pads[2].button,
+            N64Button::A as u16 | N64Button::Start as u16
+        );
+        assert_eq!((pads[2].stick_x, pads[2].stick_y), (-40, 80));
+
+        handle.release(2, N64Button::A);
+        assert_eq!(read_pads(&mut manager)[2].button, N64Button::Start as u16);
+
+        manager.disconnect_virtual_gamepad(2);
+        assert_eq!(manager.gamepad_bits(), 0);
+        assert_eq!(read_pads(&mut manager)[2].errno, CONT_NO_RESPONSE_ERROR);
+    }
+
+    #[test]
+    fn test_controller_events() {
+        let mut manager = GamepadManager::with_providers(Vec::new());
+        manager.init();
+
+        let seen = std::rc::Rc::new(std::cell::Cell::new(0));
+        let counter = seen.clone();
+        manager.set_controller_event_callback(move |_| counter.set(counter.get() + 1));
+
+        manager.connect_virtual_gamepad(1);
+        manager.connect_virtual_gamepad(3);
+        manager.disconnect_virtual_gamepad(1);
+        assert_eq!(seen.get(), 3);
+
+        let connected = |slot| ControllerEvent {
+            slot,
+            connected: true,
+        };
+        assert_eq!(manager.poll_controller_event(), Some(connected(1)));
+        assert_eq!(manager.poll_controller_event(), Some(connected(3)));
+        assert_eq!(
+            manager.poll_controller_event(),
+            Some(ControllerEvent {
+                slot: 1,
+                connected: false,
+            })
+        );
+        assert_eq!(manager.poll_controller_event(), None);
+        assert_eq!(manager.controller_name(3), Some("Virtual Gamepad 4"));
+    }
+
+    #[test]
+    fn test_record_and_replay_polls() {
+        let path = std::env::temp_dir().join(format!("helix-polls-{}.bin", std::process::id()));
+        let mut manager = GamepadManager::with_providers(Vec::new());
+        manager.init();
+        manager.connect_virtual_gamepad(0);
+        let handle = manager.virtual_gamepads();
+
+        // the reads after each poll, the game skips `osContGetReadData` on the
+        // third poll and reads the fourth one twice
+        let polls = [1, 1, 0, 2, 1];
+        let run_polls = |manager: &mut GamepadManager, stick_offset: i8| {
+            let mut reads = Vec::new();
+            for (frame, read_count) in polls.into_iter().enumerate() {
+                handle.set_stick(0, frame as i8 * 10 + stick_offset, -(frame as i8));
+                manager.process_events();
+                for _ in 0..read_count {
+                    reads.push(read_pads(manager));
+                }
+            }
+            reads
+        };
+
+        manager.start_recording(&path, "NSME").unwrap();
+        let recorded = run_polls(&mut manager, 0);
+        manager.stop_recording().unwrap();
+
+        // the live stick differs from the recording during playback
+        manager.start_playback(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(run_polls(&mut manager, 50), recorded);
+        assert!(manager.is_playing_back());
+
+        // the next poll runs past the recording and goes back to live input
+        handle.set_stick(0, 127, 127);
+        manager.process_events();
+        let pads = read_pads(&mut manager);
+        assert!(!manager.is_playing_back());
+        assert_eq!((pads[0].stick_x, pads[0].stick_y), (127, 127));
+    }
+
+    #[test]
+    fn test_poll_flushes_controller_paks() {
+        let path = std::env::temp_dir().join(format!("helix-poll-pak-{}.mpk", std::process::id()));
+        let mut manager = GamepadManager::with_providers(Vec::new());
+        manager.insert_controller_pak(1, &path).unwrap();
+
+        let pak = manager.controller_pak_mut(1).unwrap();
+        pak.set_label(b"HELIX");
+        assert!(pak.is_dirty());
+
+        manager.process_events();
+        let pak = manager.controller_pak(1).unwrap();
+        assert!(!pak.is_dirty());
+        assert_eq!(std::fs::read(&path).unwrap(), pak.data());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_last_error() {
+        let mut manager = GamepadManager::with_providers(Vec::new());
+        assert!(GamepadManagerGetLastError(Some(&mut manager)).is_null());
+
+        let mut bits = 0u8;
+        assert_eq!(
+            unsafe { GamepadManagerInit(Some(&mut manager), &mut bits) },
+            0
+        );
+
+        // not a single button bit
+        let code = GamepadManagerSetTurbo(Some(&mut manager), 0, 0x8001, 2);
+        assert_eq!(code, GAMEPAD_ERR_INVALID_ARGUMENT);
+
+        let error = GamepadManagerGetLastError(Some(&mut manager));
+        let error = unsafe { CStr::from_ptr(error) }.to_str().unwrap();
+        assert_eq!(error, "Invalid turbo button 0x8001");
+        assert_eq!(manager.last_error(), Some(error));
+    }
+
+    #[test]
+    fn test_invalid_port_bindings() {
+        let mut manager = GamepadManager::with_providers(Vec::new());
+        let a = N64Button::A as u16;
+
+        assert_eq!(
+            GamepadManagerSetTurbo(Some(&mut manager), 4, a, 2),
+            GAMEPAD_ERR_INVALID_ARGUMENT
+        );
+        assert_eq!(
+            GamepadManagerSetToggle(Some(&mut manager), 255, a, true),
+            GAMEPAD_ERR_INVALID_ARGUMENT
+        );
+        assert_eq!(manager.last_error(), Some("Invalid toggle port 255"));
+        assert!(manager.port_bindings(4).is_none());
+
+        manager.start_macro_recording(4);
+        assert!(!manager.is_recording_macro());
+        manager.process_events();
+        read_pads(&mut manager);
+    }
+
+    #[test]
+    fn test_unavailable_backend() {
+        let mut manager = GamepadManager::with_providers(Vec::new());
+        manager.provider_errors = vec!["Gamepads are unavailable".to_string()];
+
+        // the game still gets its controllers
+        let mut bits = 0u8;
+        assert_eq!(
+            unsafe { GamepadManagerInit(Some(&mut manager), &mut bits) },
+            0
+        );
+        assert_eq!(
+            GamepadManagerGetBackendStatus(Some(&mut manager)),
+            GAMEPAD_ERR_BACKEND_UNAVAILABLE
+        );
+        assert_eq!(manager.last_error(), Some("Gamepads are unavailable"));
+    }
+
+    #[test]
+    fn test_invalid_port_stick_settings() {
+        let mut manager = GamepadManager::with_providers(Vec::new());
+        let settings = StickSettings {
+            octagon_gate: false,
+            ..Default::default()
+        };
+
+        GamepadManagerSetStickSettings(Some(&mut manager), 4, settings);
+        assert_eq!(
+            GamepadManagerGetStickSettings(Some(&mut manager), 255),
+            StickSettings::default()
+        );
+    }
 }
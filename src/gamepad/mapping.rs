@@ -0,0 +1,659 @@
+use crate::gamepad::bindings::PortBindings;
+use crate::gamepad::types::{N64Button, MAXCONTROLLERS};
+use gilrs::{Axis, Button};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use winit::event::{ModifiersState, VirtualKeyCode};
+
+fn load_json<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> anyhow::Result<T> {
+    let reader = BufReader::new(File::open(path)?);
+    let value = serde_json::from_reader(reader)?;
+    Ok(value)
+}
+
+fn save_json<T: Serialize, P: AsRef<Path>>(value: &T, path: P) -> anyhow::Result<()> {
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, value)?;
+    Ok(())
+}
+
+/// Direction the analog stick is pushed towards by a digital input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StickDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl StickDirection {
+    pub const ALL: [StickDirection; 4] = [
+        StickDirection::Up,
+        StickDirection::Down,
+        StickDirection::Left,
+        StickDirection::Right,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StickAxis {
+    X,
+    Y,
+}
+
+/// An N64 input that can be driven by a digital (on/off) source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum N64Input {
+    Button(N64Button),
+    Stick(StickDirection),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AxisTarget {
+    /// Triggers the input once the axis passes the threshold, a negative
+    /// threshold triggers when the axis goes below it.
+    Digital { input: N64Input, threshold: f32 },
+    /// Drives the N64 stick axis with the value of the axis.
+    Analog { axis: StickAxis, inverted: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ButtonBinding {
+    pub button: Button,
+    pub input: N64Input,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AxisBinding {
+    pub axis: Axis,
+    pub target: AxisTarget,
+}
+
+/// Drives the four C buttons from an analog stick.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CStickMapping {
+    pub x_axis: Axis,
+    pub y_axis: Axis,
+    /// How far the stick has to be pushed before a C button is pressed.
+    pub threshold: f32,
+    /// Whether diagonals press two adjacent C buttons at once.
+    pub diagonals: bool,
+}
+
+impl Default for CStickMapping {
+    fn default() -> Self {
+        Self {
+            x_axis: Axis::RightStickX,
+            y_axis: Axis::RightStickY,
+            threshold: 0.3,
+            diagonals: false,
+        }
+    }
+}
+
+/// Accumulated N64 input produced by evaluating bindings.
+/// Stick values are in the range -1.0..=1.0.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct N64InputState {
+    pub buttons: u16,
+    pub stick_x: f32,
+    pub stick_y: f32,
+}
+
+impl N64InputState {
+    pub fn press(&mut self, input: N64Input) {
+        match input {
+            N64Input::Button(button) => self.buttons |= button as u16,
+            N64Input::Stick(StickDirection::Up) => self.stick_y = 1.0,
+            N64Input::Stick(StickDirection::Down) => self.stick_y = -1.0,
+            N64Input::Stick(StickDirection::Left) => self.stick_x = -1.0,
+            N64Input::Stick(StickDirection::Right) => self.stick_x = 1.0,
+        }
+    }
+
+    pub fn apply_axis(&mut self, target: AxisTarget, value: f32) {
+        match target {
+            AxisTarget::Digital { input, threshold } => {
+                let triggered = if threshold < 0.0 {
+                    value < threshold
+                } else {
+                    value > threshold
+                };
+
+                if triggered {
+                    self.press(input);
+                }
+            }
+            AxisTarget::Analog { axis, inverted } => {
+                let value = if inverted { -value } else { value };
+                match axis {
+                    StickAxis::X => self.stick_x = (self.stick_x + value).clamp(-1.0, 1.0),
+                    StickAxis::Y => self.stick_y = (self.stick_y + value).clamp(-1.0, 1.0),
+                }
+            }
+        }
+    }
+
+    pub fn apply_c_stick(&mut self, mapping: &CStickMapping, x: f32, y: f32) {
+        self.press_c_buttons(x, y, mapping.threshold, mapping.diagonals);
+    }
+
+    /// Presses the C buttons the point is pushed towards once it's past the threshold.
+    pub fn press_c_buttons(&mut self, x: f32, y: f32, threshold: f32, diagonals: bool) {
+        if (x.powi(2) + y.powi(2)).sqrt() <= threshold {
+            return;
+        }
+
+        let horizontal = if x > 0.0 {
+            N64Button::CRight
+        } else {
+            N64Button::CLeft
+        };
+        let vertical = if y > 0.0 {
+            N64Button::CUp
+        } else {
+            N64Button::CDown
+        };
+
+        if diagonals {
+            // split the circle in eight sectors of 45 degrees, the cardinal
+            // sectors being centered on the axes
+            let angle = y.abs().atan2(x.abs()).to_degrees();
+            if angle < 67.5 {
+                self.buttons |= horizontal as u16;
+            }
+            if angle > 22.5 {
+                self.buttons |= vertical as u16;
+            }
+        } else if x.abs() > y.abs() {
+            self.buttons |= horizontal as u16;
+        } else {
+            self.buttons |= vertical as u16;
+        }
+    }
+}
+
+/// Identifies the devices a mapping profile is tuned for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeviceMatch {
+    /// The SDL GUID of the device, as 32 hex digits.
+    Uuid(String),
+    /// Any device whose name contains the text, ignoring case.
+    Name(String),
+}
+
+impl DeviceMatch {
+    pub fn matches(&self, name: &str, uuid: &[u8; 16]) -> bool {
+        match self {
+            DeviceMatch::Uuid(guid) => {
+                let hex: String = uuid.iter().map(|byte| format!("{byte:02x}")).collect();
+                guid.eq_ignore_ascii_case(&hex)
+            }
+            DeviceMatch::Name(text) => name.to_lowercase().contains(&text.to_lowercase()),
+        }
+    }
+}
+
+/// Picks the profile made for the device, profiles matching its GUID win
+/// over the ones matching its name.
+pub fn select_device_profile(
+    profiles: &[MappingProfile],
+    name: &str,
+    uuid: &[u8; 16],
+) -> Option<usize> {
+    let find = |uuid_match: bool| {
+        profiles.iter().position(|profile| {
+            profile.devices.iter().any(|device| {
+                matches!(device, DeviceMatch::Uuid(_)) == uuid_match && device.matches(name, uuid)
+            })
+        })
+    };
+
+    find(true).or_else(|| find(false))
+}
+
+/// Describes how the inputs of a gamepad are translated into N64 inputs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MappingProfile {
+    pub name: String,
+    /// Devices the profile is picked for automatically when they connect.
+    #[serde(default)]
+    pub devices: Vec<DeviceMatch>,
+    pub buttons: Vec<ButtonBinding>,
+    pub axes: Vec<AxisBinding>,
+    #[serde(default)]
+    pub c_stick: Option<CStickMapping>,
+    /// Turbo, toggle and macro behaviors of each port.
+    #[serde(default)]
+    pub ports: [PortBindings; MAXCONTROLLERS],
+}
+
+impl MappingProfile {
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        load_json(path)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        save_json(self, path)
+    }
+
+    /// Loads every profile stored as JSON in the directory, sorted by file name.
+    pub fn load_dir<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<Self>> {
+        let mut paths = std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+        paths.sort();
+
+        paths.iter().map(Self::load).collect()
+    }
+
+    pub fn evaluate(&self, gamepad: &gilrs::Gamepad) -> N64InputState {
+        let mut state = N64InputState::default();
+
+        for binding in &self.buttons {
+            if gamepad.is_pressed(binding.button) {
+                state.press(binding.input);
+            }
+        }
+
+        for binding in &self.axes {
+            state.apply_axis(binding.target, gamepad.value(binding.axis));
+        }
+
+        if let Some(c_stick) = &self.c_stick {
+            let x = gamepad.value(c_stick.x_axis);
+            let y = gamepad.value(c_stick.y_axis);
+            state.apply_c_stick(c_stick, x, y);
+        }
+
+        state
+    }
+
+    /// The gamepad buttons currently bound to the input.
+    pub fn buttons_for(&self, input: N64Input) -> impl Iterator<Item = Button> + '_ {
+        self.buttons
+            .iter()
+            .filter(move |binding| binding.input == input)
+            .map(|binding| binding.button)
+    }
+
+    /// Binds the button to the input, replacing the previous bindings of both.
+    pub fn bind_button(&mut self, button: Button, input: N64Input) {
+        self.buttons
+            .retain(|binding| binding.button != button && binding.input != input);
+        self.buttons.push(ButtonBinding { button, input });
+    }
+}
+
+impl Default for MappingProfile {
+    fn default() -> Self {
+        let button = |button, input| ButtonBinding {
+            button,
+            input: N64Input::Button(input),
+        };
+
+        Self {
+            name: "Default".to_string(),
+            devices: Vec::new(),
+            buttons: vec![
+                button(Button::Start, N64Button::Start),
+                button(Button::LeftTrigger, N64Button::L),
+                button(Button::RightTrigger2, N64Button::R),
+                button(Button::LeftTrigger2, N64Button::Z),
+                button(Button::RightTrigger, N64Button::CRight),
+                button(Button::North, N64Button::CLeft),
+                button(Button::South, N64Button::A),
+                button(Button::West, N64Button::B),
+                button(Button::East, N64Button::CDown),
+                button(Button::DPadUp, N64Button::DUp),
+                button(Button::DPadDown, N64Button::DDown),
+                button(Button::DPadLeft, N64Button::DLeft),
+                button(Button::DPadRight, N64Button::DRight),
+            ],
+            axes: vec![
+                AxisBinding {
+                    axis: Axis::LeftStickX,
+                    target: AxisTarget::Analog {
+                        axis: StickAxis::X,
+                        inverted: false,
+                    },
+                },
+                AxisBinding {
+                    axis: Axis::LeftStickY,
+                    target: AxisTarget::Analog {
+                        axis: StickAxis::Y,
+                        inverted: false,
+                    },
+                },
+            ],
+            c_stick: Some(CStickMapping::default()),
+            ports: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub key: VirtualKeyCode,
+    /// Modifiers that have to be held for the binding to trigger.
+    #[serde(default)]
+    pub modifiers: ModifiersState,
+    pub input: N64Input,
+}
+
+/// Scales the stick range while the key is held, e.g. to walk instead of run.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StickModifier {
+    pub key: VirtualKeyCode,
+    pub scale: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MouseLookTarget {
+    Stick,
+    CButtons,
+}
+
+/// Drives the analog stick or the C buttons from mouse movement while the pointer is captured.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MouseLook {
+    pub target: MouseLookTarget,
+    /// Deflection added per unit of mouse motion, full deflection is 1.0.
+    pub sensitivity: f32,
+    /// Fraction of the previous output kept each poll, 0.0 disables smoothing.
+    pub smoothing: f32,
+    /// Fraction of the deflection kept each poll once the mouse stops,
+    /// 0.0 recenters immediately and values close to 1.0 hold the position.
+    pub decay: f32,
+    pub invert_y: bool,
+    /// How far the deflection has to go before a C button is pressed.
+    pub c_button_threshold: f32,
+    /// Key that captures and releases the pointer, F9 by default so it
+    /// doesn't also toggle the N64 Mouse (F8).
+    pub capture_key: VirtualKeyCode,
+}
+
+impl Default for MouseLook {
+    fn default() -> Self {
+        Self {
+            target: MouseLookTarget::Stick,
+            sensitivity: 0.05,
+            smoothing: 0.5,
+            decay: 0.0,
+            invert_y: false,
+            c_button_threshold: 0.3,
+            capture_key: VirtualKeyCode::F9,
+        }
+    }
+}
+
+/// The deflection produced by mouse movement, carried from one poll to the next.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct MouseLookState {
+    /// Deflection before smoothing.
+    pub deflection: (f32, f32),
+    /// Deflection handed to the game.
+    pub output: (f32, f32),
+}
+
+impl MouseLook {
+    /// Advances the deflection by one poll given the motion since the previous one.
+    pub fn update(&self, state: &mut MouseLookState, motion: (f32, f32)) {
+        // mouse motion grows downwards while the stick grows upwards
+        let y_sign = if self.invert_y { 1.0 } else { -1.0 };
+        let (x, y) = state.deflection;
+        state.deflection = (
+            (x * self.decay + motion.0 * self.sensitivity).clamp(-1.0, 1.0),
+            (y * self.decay + motion.1 * self.sensitivity * y_sign).clamp(-1.0, 1.0),
+        );
+
+        let smooth =
+            |previous: f32, value: f32| previous * self.smoothing + value * (1.0 - self.smoothing);
+        let (x, y) = state.output;
+        state.output = (smooth(x, state.deflection.0), smooth(y, state.deflection.1));
+    }
+
+    /// Applies the deflection to the state.
+    pub fn apply(&self, state: &mut N64InputState, x: f32, y: f32) {
+        match self.target {
+            MouseLookTarget::Stick => {
+                state.stick_x = (state.stick_x + x).clamp(-1.0, 1.0);
+                state.stick_y = (state.stick_y + y).clamp(-1.0, 1.0);
+            }
+            MouseLookTarget::CButtons => {
+                state.press_c_buttons(x, y, self.c_button_threshold, false)
+            }
+        }
+    }
+}
+
+/// Describes how keyboard keys are translated into N64 inputs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyboardMapping {
+    pub name: String,
+    pub bindings: Vec<KeyBinding>,
+    #[serde(default)]
+    pub stick_modifiers: Vec<StickModifier>,
+    #[serde(default)]
+    pub mouse_look: Option<MouseLook>,
+}
+
+impl KeyboardMapping {
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        load_json(path)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        save_json(self, path)
+    }
+
+    /// Evaluates the bindings for the held keys. When several bindings share a key,
+    /// only the ones requiring the most modifiers among those currently held trigger.
+    pub fn evaluate(&self, keys: &[VirtualKeyCode], modifiers: ModifiersState) -> N64InputState {
+        let mut state = N64InputState::default();
+
+        for key in keys {
+            let candidates = self
+                .bindings
+                .iter()
+                .filter(|binding| binding.key == *key && modifiers.contains(binding.modifiers));
+
+            let specificity = candidates
+                .clone()
+                .map(|binding| binding.modifiers.bits().count_ones())
+                .max();
+
+            for binding in candidates {
+                if Some(binding.modifiers.bits().count_ones()) == specificity {
+                    state.press(binding.input);
+                }
+            }
+        }
+
+        let scale = self
+            .stick_modifiers
+            .iter()
+            .filter(|modifier| keys.contains(&modifier.key))
+            .fold(1.0, |acc, modifier| acc * modifier.scale);
+
+        state.stick_x *= scale;
+        state.stick_y *= scale;
+
+        state
+    }
+}
+
+impl Default for KeyboardMapping {
+    fn default() -> Self {
+        let key = |key, input| KeyBinding {
+            key,
+            modifiers: ModifiersState::empty(),
+            input,
+        };
+
+        Self {
+            name: "Default".to_string(),
+            bindings: vec![
+                key(VirtualKeyCode::X, N64Input::Button(N64Button::A)),
+                key(VirtualKeyCode::C, N64Input::Button(N64Button::B)),
+                key(VirtualKeyCode::Z, N64Input::Button(N64Button::Z)),
+                key(VirtualKeyCode::Space, N64Input::Button(N64Button::Start)),
+                key(VirtualKeyCode::W, N64Input::Stick(StickDirection::Up)),
+                key(VirtualKeyCode::A, N64Input::Stick(StickDirection::Left)),
+                key(VirtualKeyCode::S, N64Input::Stick(StickDirection::Down)),
+                key(VirtualKeyCode::D, N64Input::Stick(StickDirection::Right)),
+                key(VirtualKeyCode::Up, N64Input::Button(N64Button::CUp)),
+                key(VirtualKeyCode::Left, N64Input::Button(N64Button::CLeft)),
+                key(VirtualKeyCode::Down, N64Input::Button(N64Button::CDown)),
+                key(VirtualKeyCode::Right, N64Input::Button(N64Button::CRight)),
+                key(VirtualKeyCode::T, N64Input::Button(N64Button::DUp)),
+                key(VirtualKeyCode::F, N64Input::Button(N64Button::DLeft)),
+                key(VirtualKeyCode::G, N64Input::Button(N64Button::DDown)),
+                key(VirtualKeyCode::H, N64Input::Button(N64Button::DRight)),
+                key(VirtualKeyCode::R, N64Input::Button(N64Button::L)),
+                key(VirtualKeyCode::Y, N64Input::Button(N64Button::R)),
+            ],
+            stick_modifiers: vec![StickModifier {
+                key: VirtualKeyCode::LShift,
+                scale: 0.5,
+            }],
+            mouse_look: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_round_trip() {
+        let profile = MappingProfile::default();
+        let json = serde_json::to_string(&profile).unwrap();
+        let decoded: MappingProfile = serde_json::from_str(&json).unwrap();
+        assert_eq!(profile, decoded);
+    }
+
+    #[test]
+    fn test_bind_button() {
+        let mut profile = MappingProfile::default();
+        let a = N64Input::Button(N64Button::A);
+
+        profile.bind_button(Button::East, a);
+        assert_eq!(profile.buttons_for(a).collect::<Vec<_>>(), [Button::East]);
+        assert_eq!(
+            profile
+                .buttons_for(N64Input::Button(N64Button::CDown))
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_select_device_profile() {
+        let profile = |devices| MappingProfile {
+            devices,
+            ..Default::default()
+        };
+
+        let uuid = [
+            0x03, 0x00, 0xc1, 0x2a, 0x5e, 0x04, 0, 0, 0xe0, 0x02, 0, 0, 0, 0, 0, 0,
+        ];
+        let profiles = [
+            profile(vec![DeviceMatch::Name("xbox".to_string())]),
+            profile(vec![DeviceMatch::Uuid(
+                "0300C12A5E040000E002000000000000".to_string(),
+            )]),
+        ];
+
+        assert_eq!(
+            select_device_profile(&profiles, "Xbox Wireless Controller", &uuid),
+            Some(1)
+        );
+        assert_eq!(
+            select_device_profile(&profiles, "Xbox Wireless Controller", &[0; 16]),
+            Some(0)
+        );
+        assert_eq!(
+            select_device_profile(&profiles, "DualSense", &[0; 16]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_c_stick_diagonals() {
+        let mut mapping = CStickMapping::default();
+
+        let mut state = N64InputState::default();
+        state.apply_c_stick(&mapping, 0.7, 0.6);
+        assert_eq!(state.buttons, N64Button::CRight as u16);
+
+        mapping.diagonals = true;
+
+        let mut state = N64InputState::default();
+        state.apply_c_stick(&mapping, 0.7, 0.7);
+        assert_eq!(
+            state.buttons,
+            N64Button::CRight as u16 | N64Button::CUp as u16
+        );
+
+        let mut state = N64InputState::default();
+        state.apply_c_stick(&mapping, -0.2, -0.9);
+        assert_eq!(state.buttons, N64Button::CDown as u16);
+    }
+
+    #[test]
+    fn test_keyboard_chords() {
+        let mut mapping = KeyboardMapping::default();
+        mapping.bindings.push(KeyBinding {
+            key: VirtualKeyCode::X,
+            modifiers: ModifiersState::CTRL,
+            input: N64Input::Button(N64Button::Start),
+        });
+
+        let keys = [VirtualKeyCode::X];
+        let state = mapping.evaluate(&keys, ModifiersState::empty());
+        assert_eq!(state.buttons, N64Button::A as u16);
+        let state = mapping.evaluate(&keys, ModifiersState::CTRL);
+        assert_eq!(state.buttons, N64Button::Start as u16);
+    }
+
+    #[test]
+    fn test_mouse_look() {
+        let mouse_look = MouseLook {
+            smoothing: 0.0,
+            decay: 0.5,
+            ..Default::default()
+        };
+
+        // moving the mouse up and right pushes the stick up and right
+        let mut look = MouseLookState::default();
+        mouse_look.update(&mut look, (10.0, -4.0));
+        assert_eq!(look.deflection, (0.5, 0.2));
+        assert_eq!(look.output, look.deflection);
+
+        // once the mouse stops the deflection decays back to the center
+        mouse_look.update(&mut look, (0.0, 0.0));
+        assert_eq!(look.output, (0.25, 0.1));
+
+        let mut state = N64InputState::default();
+        let mouse_look = MouseLook {
+            target: MouseLookTarget::CButtons,
+            ..mouse_look
+        };
+        mouse_look.apply(&mut state, -0.5, 0.1);
+        assert_eq!(state.buttons, N64Button::CLeft as u16);
+    }
+
+    #[test]
+    fn test_keyboard_walk_modifier() {
+        let mapping = KeyboardMapping::default();
+        let keys = [VirtualKeyCode::W, VirtualKeyCode::LShift];
+        let state = mapping.evaluate(&keys, ModifiersState::SHIFT);
+        assert_eq!((state.stick_x, state.stick_y), (0.0, 0.5));
+    }
+}
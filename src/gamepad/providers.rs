@@ -1,31 +1,142 @@
-use crate::gamepad::types::OSControllerPad;
-use ::gilrs::GamepadId;
-use winit::event::{KeyboardInput, ModifiersState};
+use crate::gamepad::mapping::{KeyboardMapping, MappingProfile};
+use crate::gamepad::types::{OSControllerPad, CONT_TYPE_MOUSE, CONT_TYPE_NORMAL};
+use crate::gamepad::utils::StickSettings;
+use ::gilrs::{Button, GamepadId};
+use mouse::MouseSettings;
+use winit::event::{DeviceEvent, WindowEvent};
 
 pub mod gilrs;
 pub mod keyboard;
+pub mod mouse;
+pub mod virtual_gamepad;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GamepadService {
     GilRs(GamepadId),
     Keyboard(),
+    Virtual(u8),
+    Mouse(u8),
+}
+
+/// A change in the devices a provider exposes since the last call to `process_events`.
+pub enum HotplugEvent {
+    Connected(Gamepad),
+    Disconnected(GamepadService),
+}
+
+/// The input of a single device, as read by its provider.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct GamepadInput {
+    pub connected: bool,
+    pub buttons: u16,
+    /// The stick position in N64 range, `None` while the device isn't deflecting it.
+    pub stick: Option<(i8, i8)>,
+    /// The analog left and right triggers in the range 0-1, if the device has them.
+    pub triggers: (f32, f32),
+}
+
+impl GamepadInput {
+    /// Merges the input into a pad shared with the other devices on the same port.
+    pub fn apply(&self, pad: &mut OSControllerPad) {
+        if !self.connected {
+            return;
+        }
+
+        pad.button |= self.buttons;
+
+        if let Some((x, y)) = self.stick {
+            if x != 0 {
+                pad.stick_x = x;
+            }
+            if y != 0 {
+                pad.stick_y = y;
+            }
+        }
+    }
 }
 
 pub trait GamepadProvider {
-    fn scan(&self) -> Vec<Gamepad>;
-    fn process_events(&mut self);
-    unsafe fn read(&self, controllers: &Gamepad, pad: *mut OSControllerPad);
+    /// Lists every device currently available.
+    fn scan(&mut self) -> Vec<Gamepad>;
+    /// Handles pending events and reports the devices that came and went since the last call.
+    fn process_events(&mut self) -> Vec<HotplugEvent>;
+    /// Called once per controller poll, before the devices are read.
+    fn begin_poll(&mut self) {}
+    /// Reads the input of one of the devices reported by this provider.
+    fn read(&self, controller: &Gamepad) -> GamepadInput;
+
+    fn handle_window_event(&mut self, _event: &WindowEvent) {}
+    fn handle_device_event(&mut self, _event: &DeviceEvent) {}
+
+    /// Whether the provider needs the pointer to be captured by the window.
+    fn wants_pointer_capture(&self) -> bool {
+        false
+    }
+
+    /// Returns the last gamepad button pressed since the previous call, used to bind buttons.
+    fn take_pressed_button(&mut self) -> Option<Button> {
+        None
+    }
+
+    fn set_mapping_profile(&mut self, _profile: &MappingProfile) {}
+    /// Sets the profiles picked automatically for the devices they're made for.
+    fn set_device_profiles(&mut self, _profiles: &[MappingProfile]) {}
+    fn set_keyboard_mapping(&mut self, _mapping: &KeyboardMapping) {}
+    fn set_mouse_settings(&mut self, _settings: &MouseSettings) {}
+
+    /// Adds SDL GameController mappings (in the `gamecontrollerdb.txt` format),
+    /// the devices have to be scanned again afterwards.
+    fn add_controller_mappings(&mut self, _mappings: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
 
-    fn handle_keyboard_input(&mut self, input: KeyboardInput);
-    fn handle_modifiers_changed(&mut self, modifiers: ModifiersState);
+    fn supports_rumble(&self, _controller: &Gamepad) -> bool {
+        false
+    }
+
+    fn set_rumble(&mut self, _controller: &Gamepad, _strength: f32) {}
 }
 
 pub struct Gamepad {
-    pub _slot: u8,
+    pub slot: u8,
     pub service: GamepadService,
+    pub name: String,
+    /// Identifies the model of the device, used to give it back its port when it reconnects.
+    pub uuid: [u8; 16],
+    pub stick_settings: StickSettings,
+    /// The device profile picked for the device, `None` when it uses the active mapping profile.
+    pub profile: Option<String>,
+    /// Index of the provider that reported the device, assigned by the manager.
+    pub(crate) provider: usize,
 }
 
 impl Gamepad {
     pub fn new(service: GamepadService) -> Self {
-        Self { _slot: 0, service }
+        let name = match service {
+            GamepadService::GilRs(_) => "Gamepad".to_string(),
+            GamepadService::Keyboard() => "Keyboard".to_string(),
+            GamepadService::Virtual(slot) => format!("Virtual Gamepad {}", slot + 1),
+            GamepadService::Mouse(_) => "Mouse".to_string(),
+        };
+
+        Self {
+            slot: 0,
+            service,
+            name,
+            uuid: [0; 16],
+            stick_settings: StickSettings::default(),
+            profile: None,
+            provider: 0,
+        }
+    }
+
+    /// The libultra controller type reported for the device.
+    pub fn controller_type(&self) -> u16 {
+        match self.service {
+            GamepadService::GilRs(_) | GamepadService::Keyboard() | GamepadService::Virtual(_) => {
+                CONT_TYPE_NORMAL
+            }
+            GamepadService::Mouse(_) => CONT_TYPE_MOUSE,
+        }
     }
 }
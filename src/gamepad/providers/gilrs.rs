@@ -1,110 +1,256 @@
-use crate::gamepad::providers::{Gamepad, GamepadProvider, GamepadService};
-use crate::gamepad::types::{N64Button, OSControllerPad};
+use crate::gamepad::mapping::{select_device_profile, MappingProfile};
+use crate::gamepad::providers::{
+    Gamepad, GamepadInput, GamepadProvider, GamepadService, HotplugEvent,
+};
 use crate::gamepad::utils::map_stick_value_to_n64;
-use gilrs::{Axis, Button, Gilrs};
-use log::debug;
+use gilrs::ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder};
+use gilrs::{Button, EventType, GamepadId, Gilrs, GilrsBuilder};
+use log::{debug, info, warn};
+use std::collections::HashMap;
+
+/// The rumble effect uploaded to a device, it's played at full magnitude
+/// scaled by its gain.
+struct Rumble {
+    effect: Effect,
+    // 0 while stopped
+    strength: f32,
+}
 
 pub struct GirlsGamepadProvider {
     pub api: Gilrs,
+    pub profile: MappingProfile,
+    device_profiles: Vec<MappingProfile>,
+    // index of the device profile picked for each device
+    assigned: HashMap<GamepadId, usize>,
+    // SDL mappings added on top of the ones bundled with gilrs
+    mappings: String,
+    effects: HashMap<GamepadId, Rumble>,
+    pressed: Option<Button>,
 }
 
 impl GirlsGamepadProvider {
-    pub fn new() -> Self {
-        let api = Gilrs::new().unwrap();
-        Self { api }
+    /// Fails when the platform has no gamepad backend, e.g. udev/evdev isn't available.
+    pub fn new() -> anyhow::Result<Self> {
+        // gilrs' error holds a dummy context that isn't thread safe, so only its message is kept
+        let api = Gilrs::new().map_err(|e| anyhow::anyhow!("{e}"))?;
+        Ok(Self {
+            api,
+            profile: MappingProfile::default(),
+            device_profiles: Vec::new(),
+            assigned: HashMap::new(),
+            mappings: String::new(),
+            effects: HashMap::new(),
+            pressed: None,
+        })
     }
-}
 
-impl Default for GirlsGamepadProvider {
-    fn default() -> Self {
-        Self::new()
+    fn device(&mut self, id: GamepadId) -> Gamepad {
+        let gamepad = self.api.gamepad(id);
+
+        let mut device = Gamepad::new(GamepadService::GilRs(id));
+        device.name = gamepad.name().to_string();
+        device.uuid = gamepad.uuid();
+
+        self.assigned.remove(&id);
+        if let Some(index) =
+            select_device_profile(&self.device_profiles, &device.name, &device.uuid)
+        {
+            let profile = &self.device_profiles[index];
+            info!("Using mapping profile {} for {}", profile.name, device.name);
+            device.profile = Some(profile.name.clone());
+            self.assigned.insert(id, index);
+        }
+
+        device
+    }
+
+    fn profile_for(&self, id: GamepadId) -> &MappingProfile {
+        self.assigned
+            .get(&id)
+            .and_then(|index| self.device_profiles.get(*index))
+            .unwrap_or(&self.profile)
     }
 }
 
 impl GamepadProvider for GirlsGamepadProvider {
-    fn scan(&self) -> Vec<Gamepad> {
-        let mut devices: Vec<Gamepad> = Vec::new();
+    fn scan(&mut self) -> Vec<Gamepad> {
+        let ids: Vec<GamepadId> = self.api.gamepads().map(|(id, _)| id).collect();
+
+        ids.into_iter()
+            .map(|id| {
+                debug!("Found gamepad: {}", self.api.gamepad(id).name());
+                self.device(id)
+            })
+            .collect()
+    }
 
-        for (id, gamepad) in self.api.gamepads() {
-            debug!("Found gamepad: {}", gamepad.name());
-            devices.push(Gamepad::new(GamepadService::GilRs(id)));
+    fn process_events(&mut self) -> Vec<HotplugEvent> {
+        let mut events = Vec::new();
+
+        while let Some(event) = self.api.next_event() {
+            match event.event {
+                EventType::Connected => {
+                    debug!("Gamepad connected: {}", self.api.gamepad(event.id).name());
+                    events.push(HotplugEvent::Connected(self.device(event.id)));
+                }
+                EventType::Disconnected => {
+                    debug!(
+                        "Gamepad disconnected: {}",
+                        self.api.gamepad(event.id).name()
+                    );
+                    self.effects.remove(&event.id);
+                    self.assigned.remove(&event.id);
+                    events.push(HotplugEvent::Disconnected(GamepadService::GilRs(event.id)));
+                }
+                EventType::ButtonPressed(button, _) if button != Button::Unknown => {
+                    self.pressed = Some(button);
+                }
+                _ => {}
+            }
+        }
+
+        events
+    }
+
+    fn read(&self, controller: &Gamepad) -> GamepadInput {
+        let GamepadService::GilRs(gamepad_id) = controller.service else {
+            return GamepadInput::default();
+        };
+
+        let gamepad = self.api.gamepad(gamepad_id);
+        if !gamepad.is_connected() {
+            debug!("Gamepad is not connected");
+            return GamepadInput::default();
         }
 
-        devices
+        let state = self.profile_for(gamepad_id).evaluate(&gamepad);
+        let trigger = |button| gamepad.button_data(button).map_or(0.0, |data| data.value());
+
+        GamepadInput {
+            connected: true,
+            buttons: state.buttons,
+            stick: map_stick_value_to_n64(
+                state.stick_x,
+                state.stick_y,
+                1.0,
+                &controller.stick_settings,
+            ),
+            triggers: (
+                trigger(Button::LeftTrigger2),
+                trigger(Button::RightTrigger2),
+            ),
+        }
+    }
+
+    fn take_pressed_button(&mut self) -> Option<Button> {
+        self.pressed.take()
+    }
+
+    fn set_mapping_profile(&mut self, profile: &MappingProfile) {
+        self.profile = profile.clone();
     }
 
-    fn process_events(&mut self) {
-        while let Some(_ev) = self.api.next_event() {}
+    fn set_device_profiles(&mut self, profiles: &[MappingProfile]) {
+        self.device_profiles = profiles.to_vec();
+        self.assigned.clear();
     }
 
-    unsafe fn read(&self, controller: &Gamepad, pad: *mut OSControllerPad) {
-        if let GamepadService::GilRs(gamepad_id) = controller.service {
-            let gamepad = self.api.gamepad(gamepad_id);
+    fn add_controller_mappings(&mut self, mappings: &str) -> anyhow::Result<()> {
+        let mut combined = self.mappings.clone();
+        combined.push_str(mappings);
+        combined.push('\n');
 
-            if !gamepad.is_connected() {
-                debug!("Gamepad is not connected");
+        // gilrs only reads mappings when it's created, so it's created anew
+        // with every mapping added so far
+        let api = GilrsBuilder::new()
+            .add_mappings(&combined)
+            .build()
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+
+        for (_, rumble) in self.effects.drain() {
+            _ = rumble.effect.stop();
+        }
+        self.api = api;
+        self.mappings = combined;
+        self.assigned.clear();
+        self.pressed = None;
+        Ok(())
+    }
+
+    fn supports_rumble(&self, controller: &Gamepad) -> bool {
+        match controller.service {
+            GamepadService::GilRs(gamepad_id) => self.api.gamepad(gamepad_id).is_ff_supported(),
+            _ => false,
+        }
+    }
+
+    fn set_rumble(&mut self, controller: &Gamepad, strength: f32) {
+        let GamepadService::GilRs(gamepad_id) = controller.service else {
+            return;
+        };
+
+        // games pulse the motor every few frames, only changes reach the device
+        let strength = strength.clamp(0.0, 1.0);
+        let current = self
+            .effects
+            .get(&gamepad_id)
+            .map_or(0.0, |rumble| rumble.strength);
+        if strength == current {
+            return;
+        }
+
+        // the effect is uploaded once per device and reused afterwards
+        if !self.effects.contains_key(&gamepad_id) {
+            if !self.supports_rumble(controller) {
                 return;
             }
 
-            unsafe {
-                if gamepad.is_pressed(Button::Start) {
-                    (*pad).button |= N64Button::Start as u16;
-                }
-                if gamepad.is_pressed(Button::LeftTrigger) {
-                    (*pad).button |= N64Button::L as u16;
-                }
-                if gamepad.is_pressed(Button::RightTrigger2) {
-                    (*pad).button |= N64Button::R as u16;
-                }
-                if gamepad.is_pressed(Button::LeftTrigger2) {
-                    (*pad).button |= N64Button::Z as u16;
-                }
-                if gamepad.is_pressed(Button::RightTrigger) {
-                    (*pad).button |= N64Button::CRight as u16;
-                }
-                if gamepad.is_pressed(Button::North) {
-                    (*pad).button |= N64Button::CLeft as u16;
-                }
-                if gamepad.is_pressed(Button::South) {
-                    (*pad).button |= N64Button::A as u16;
-                }
-                if gamepad.is_pressed(Button::West) {
-                    (*pad).button |= N64Button::B as u16;
-                }
-                if gamepad.is_pressed(Button::East) {
-                    (*pad).button |= N64Button::CDown as u16;
-                }
-                if gamepad.is_pressed(Button::DPadUp) {
-                    (*pad).button |= N64Button::DUp as u16;
-                }
-                if gamepad.is_pressed(Button::DPadDown) {
-                    (*pad).button |= N64Button::DDown as u16;
-                }
-                if gamepad.is_pressed(Button::DPadLeft) {
-                    (*pad).button |= N64Button::DLeft as u16;
+            let effect = EffectBuilder::new()
+                .add_effect(BaseEffect {
+                    kind: BaseEffectType::Strong {
+                        magnitude: u16::MAX,
+                    },
+                    ..Default::default()
+                })
+                .gamepads(&[gamepad_id])
+                .finish(&mut self.api);
+
+            match effect {
+                Ok(effect) => {
+                    self.effects.insert(
+                        gamepad_id,
+                        Rumble {
+                            effect,
+                            strength: 0.0,
+                        },
+                    );
                 }
-                if gamepad.is_pressed(Button::DPadRight) {
-                    (*pad).button |= N64Button::DRight as u16;
+                Err(e) => {
+                    warn!("Error creating rumble effect: {e}");
+                    return;
                 }
+            }
+        }
 
-                let left_x = gamepad.value(Axis::LeftStickX);
-                let left_y = gamepad.value(Axis::LeftStickY);
-                let _right_x = gamepad.value(Axis::RightStickX);
-                let right_y = gamepad.value(Axis::RightStickY);
+        let Some(rumble) = self.effects.get_mut(&gamepad_id) else {
+            return;
+        };
 
-                if right_y > 0.3 {
-                    (*pad).button |= N64Button::CUp as u16;
+        let result = if strength <= 0.0 {
+            rumble.effect.stop()
+        } else {
+            rumble.effect.set_gain(strength).and_then(|_| {
+                if rumble.strength <= 0.0 {
+                    rumble.effect.play()
+                } else {
+                    Ok(())
                 }
+            })
+        };
 
-                if let Some((adjusted_x, adjusted_y)) = map_stick_value_to_n64(left_x, left_y, 1.0)
-                {
-                    (*pad).stick_x = adjusted_x;
-                    (*pad).stick_y = adjusted_y;
-                }
-            }
+        if let Err(e) = result {
+            warn!("Error updating rumble effect: {e}");
         }
+        rumble.strength = strength;
     }
-
-    fn handle_keyboard_input(&mut self, _input: winit::event::KeyboardInput) {}
-    fn handle_modifiers_changed(&mut self, _modifiers: winit::event::ModifiersState) {}
 }
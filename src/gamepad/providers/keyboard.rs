@@ -1,15 +1,38 @@
-use crate::gamepad::providers::{Gamepad, GamepadProvider, GamepadService};
-use crate::gamepad::types::{N64Button, OSControllerPad};
-use crate::gamepad::utils::MAX_N64_AXIS_RANGE;
-use winit::event::VirtualKeyCode;
+use crate::gamepad::mapping::{KeyboardMapping, MouseLookState};
+use crate::gamepad::providers::{
+    Gamepad, GamepadInput, GamepadProvider, GamepadService, HotplugEvent,
+};
+use crate::gamepad::utils::{limit_to_octagon, MAX_N64_AXIS_RANGE};
+use winit::event::{DeviceEvent, ElementState, ModifiersState, VirtualKeyCode, WindowEvent};
 
 pub struct KeyboardGamepadProvider {
     pub keys: Vec<VirtualKeyCode>,
+    pub modifiers: ModifiersState,
+    pub mapping: KeyboardMapping,
+    captured: bool,
+    // mouse motion gathered since the last poll
+    motion: (f64, f64),
+    look: MouseLookState,
 }
 
 impl KeyboardGamepadProvider {
     pub fn new() -> Self {
-        Self { keys: Vec::new() }
+        Self {
+            keys: Vec::new(),
+            modifiers: ModifiersState::empty(),
+            mapping: KeyboardMapping::default(),
+            captured: false,
+            motion: (0.0, 0.0),
+            look: MouseLookState::default(),
+        }
+    }
+
+    fn set_captured(&mut self, captured: bool) {
+        self.captured = captured && self.mapping.mouse_look.is_some();
+        if !self.captured {
+            self.motion = (0.0, 0.0);
+            self.look = MouseLookState::default();
+        }
     }
 }
 
@@ -20,81 +43,90 @@ impl Default for KeyboardGamepadProvider {
 }
 
 impl GamepadProvider for KeyboardGamepadProvider {
-    fn scan(&self) -> Vec<Gamepad> {
+    fn scan(&mut self) -> Vec<Gamepad> {
         let device = Gamepad::new(GamepadService::Keyboard());
         vec![device]
     }
 
-    fn process_events(&mut self) {}
+    fn process_events(&mut self) -> Vec<HotplugEvent> {
+        Vec::new()
+    }
 
-    unsafe fn read(&self, _controllers: &Gamepad, pad: *mut OSControllerPad) {
-        unsafe {
-            if self.keys.contains(&VirtualKeyCode::X) {
-                (*pad).button |= N64Button::A as u16;
-            }
-            if self.keys.contains(&VirtualKeyCode::C) {
-                (*pad).button |= N64Button::B as u16;
-            }
-            if self.keys.contains(&VirtualKeyCode::Z) {
-                (*pad).button |= N64Button::Z as u16;
-            }
-            if self.keys.contains(&VirtualKeyCode::Space) {
-                (*pad).button |= N64Button::Start as u16;
-            }
-            if self.keys.contains(&VirtualKeyCode::W) {
-                (*pad).stick_y = MAX_N64_AXIS_RANGE as i8;
-            }
-            if self.keys.contains(&VirtualKeyCode::A) {
-                (*pad).stick_x = (-MAX_N64_AXIS_RANGE) as i8;
-            }
-            if self.keys.contains(&VirtualKeyCode::S) {
-                (*pad).stick_y = (-MAX_N64_AXIS_RANGE) as i8;
-            }
-            if self.keys.contains(&VirtualKeyCode::D) {
-                (*pad).stick_x = MAX_N64_AXIS_RANGE as i8;
-            }
-            if self.keys.contains(&VirtualKeyCode::Up) {
-                (*pad).button |= N64Button::CUp as u16;
-            }
-            if self.keys.contains(&VirtualKeyCode::Left) {
-                (*pad).button |= N64Button::CLeft as u16;
-            }
-            if self.keys.contains(&VirtualKeyCode::Down) {
-                (*pad).button |= N64Button::CDown as u16;
-            }
-            if self.keys.contains(&VirtualKeyCode::Right) {
-                (*pad).button |= N64Button::CRight as u16;
-            }
-            if self.keys.contains(&VirtualKeyCode::T) {
-                (*pad).button |= N64Button::DUp as u16;
-            }
-            if self.keys.contains(&VirtualKeyCode::F) {
-                (*pad).button |= N64Button::DLeft as u16;
-            }
-            if self.keys.contains(&VirtualKeyCode::G) {
-                (*pad).button |= N64Button::DDown as u16;
-            }
-            if self.keys.contains(&VirtualKeyCode::H) {
-                (*pad).button |= N64Button::DRight as u16;
-            }
-            if self.keys.contains(&VirtualKeyCode::R) {
-                (*pad).button |= N64Button::L as u16;
-            }
-            if self.keys.contains(&VirtualKeyCode::Y) {
-                (*pad).button |= N64Button::R as u16;
-            }
+    fn read(&self, controller: &Gamepad) -> GamepadInput {
+        if !matches!(controller.service, GamepadService::Keyboard()) {
+            return GamepadInput::default();
+        }
+
+        let mut state = self.mapping.evaluate(&self.keys, self.modifiers);
+        if let Some(mouse_look) = self.mapping.mouse_look.as_ref() {
+            let (x, y) = self.look.output;
+            mouse_look.apply(&mut state, x, y);
+        }
+
+        let range = MAX_N64_AXIS_RANGE * controller.stick_settings.range_scale;
+        let (mut stick_x, mut stick_y) = (state.stick_x * range, state.stick_y * range);
+        if controller.stick_settings.octagon_gate {
+            (stick_x, stick_y) = limit_to_octagon(stick_x, stick_y);
+        }
+
+        GamepadInput {
+            connected: true,
+            buttons: state.buttons,
+            stick: (state.stick_x != 0.0 || state.stick_y != 0.0)
+                .then_some((stick_x as i8, stick_y as i8)),
+            triggers: (0.0, 0.0),
+        }
+    }
+
+    fn begin_poll(&mut self) {
+        if let Some(mouse_look) = self.mapping.mouse_look.as_ref() {
+            let motion = (self.motion.0 as f32, self.motion.1 as f32);
+            mouse_look.update(&mut self.look, motion);
+            self.motion = (0.0, 0.0);
         }
     }
 
-    fn handle_modifiers_changed(&mut self, _modifiers: winit::event::ModifiersState) {}
+    fn handle_window_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = *modifiers,
+            WindowEvent::KeyboardInput { input, .. } => {
+                let Some(key) = input.virtual_keycode else {
+                    return;
+                };
+
+                if input.state == ElementState::Pressed {
+                    let capture_key = self.mapping.mouse_look.map(|look| look.capture_key);
+                    if capture_key == Some(key) && !self.keys.contains(&key) {
+                        self.set_captured(!self.captured);
+                    }
+
+                    if !self.keys.contains(&key) {
+                        self.keys.push(key);
+                    }
+                } else {
+                    self.keys.retain(|&k| k != key);
+                }
+            }
+            WindowEvent::Focused(false) => self.set_captured(false),
+            _ => {}
+        }
+    }
 
-    fn handle_keyboard_input(&mut self, input: winit::event::KeyboardInput) {
-        if input.state == winit::event::ElementState::Pressed {
-            if let Some(key) = input.virtual_keycode {
-                self.keys.push(key);
+    fn handle_device_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta: (x, y) } = event {
+            if self.captured {
+                self.motion.0 += x;
+                self.motion.1 += y;
             }
-        } else if let Some(key) = input.virtual_keycode {
-            self.keys.retain(|&k| k != key);
         }
     }
+
+    fn wants_pointer_capture(&self) -> bool {
+        self.captured
+    }
+
+    fn set_keyboard_mapping(&mut self, mapping: &KeyboardMapping) {
+        self.mapping = mapping.clone();
+        self.set_captured(self.captured);
+    }
 }
@@ -0,0 +1,204 @@
+use crate::gamepad::providers::{
+    Gamepad, GamepadInput, GamepadProvider, GamepadService, HotplugEvent,
+};
+use crate::gamepad::types::{N64Button, MAXCONTROLLERS};
+use serde::{Deserialize, Serialize};
+use winit::event::{DeviceEvent, ElementState, MouseButton, VirtualKeyCode, WindowEvent};
+
+/// Describes how the host mouse is exposed to the game as an N64 Mouse.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MouseSettings {
+    /// The port the mouse is plugged into, `None` leaves it unplugged.
+    pub slot: Option<u8>,
+    /// Multiplier applied to the host motion before it's reported.
+    pub sensitivity: f32,
+    /// Key that captures and releases the pointer.
+    pub capture_key: VirtualKeyCode,
+}
+
+impl Default for MouseSettings {
+    fn default() -> Self {
+        Self {
+            slot: None,
+            sensitivity: 1.0,
+            capture_key: VirtualKeyCode::F8,
+        }
+    }
+}
+
+pub struct MouseGamepadProvider {
+    pub settings: MouseSettings,
+    captured: bool,
+    buttons: u16,
+    // motion gathered since the last poll, the fraction is carried over
+    motion: (f64, f64),
+    delta: (i8, i8),
+    reported: Option<u8>,
+}
+
+impl MouseGamepadProvider {
+    pub fn new() -> Self {
+        Self {
+            settings: MouseSettings::default(),
+            captured: false,
+            buttons: 0,
+            motion: (0.0, 0.0),
+            delta: (0, 0),
+            reported: None,
+        }
+    }
+
+    fn slot(&self) -> Option<u8> {
+        self.settings
+            .slot
+            .filter(|slot| (*slot as usize) < MAXCONTROLLERS)
+    }
+
+    fn set_captured(&mut self, captured: bool) {
+        self.captured = captured && self.slot().is_some();
+        if !self.captured {
+            self.buttons = 0;
+            self.motion = (0.0, 0.0);
+        }
+    }
+}
+
+impl Default for MouseGamepadProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GamepadProvider for MouseGamepadProvider {
+    fn scan(&mut self) -> Vec<Gamepad> {
+        self.reported = self.slot();
+        self.reported
+            .map(|slot| Gamepad::new(GamepadService::Mouse(slot)))
+            .into_iter()
+            .collect()
+    }
+
+    fn process_events(&mut self) -> Vec<HotplugEvent> {
+        let slot = self.slot();
+        if slot == self.reported {
+            return Vec::new();
+        }
+
+        let mut events = Vec::new();
+        if let Some(previous) = self.reported {
+            events.push(HotplugEvent::Disconnected(GamepadService::Mouse(previous)));
+        }
+        if let Some(slot) = slot {
+            events.push(HotplugEvent::Connected(Gamepad::new(
+                GamepadService::Mouse(slot),
+            )));
+        }
+
+        self.reported = slot;
+        events
+    }
+
+    fn begin_poll(&mut self) {
+        // each poll reports the motion since the previous one
+        let (x, y) = self.motion;
+        let (whole_x, whole_y) = (x.trunc(), y.trunc());
+        self.motion = (x - whole_x, y - whole_y);
+        self.delta = (
+            whole_x.clamp(i8::MIN as f64, i8::MAX as f64) as i8,
+            whole_y.clamp(i8::MIN as f64, i8::MAX as f64) as i8,
+        );
+    }
+
+    fn read(&self, controller: &Gamepad) -> GamepadInput {
+        if !matches!(controller.service, GamepadService::Mouse(_)) {
+            return GamepadInput::default();
+        }
+
+        GamepadInput {
+            connected: true,
+            buttons: self.buttons,
+            stick: Some(self.delta),
+            triggers: (0.0, 0.0),
+        }
+    }
+
+    fn handle_window_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput { input, .. }
+                if input.state == ElementState::Pressed
+                    && input.virtual_keycode == Some(self.settings.capture_key) =>
+            {
+                self.set_captured(!self.captured);
+            }
+            WindowEvent::Focused(false) => self.set_captured(false),
+            WindowEvent::MouseInput { state, button, .. } if self.captured => {
+                let mask = match button {
+                    MouseButton::Left => N64Button::A as u16,
+                    MouseButton::Right => N64Button::B as u16,
+                    _ => return,
+                };
+
+                if *state == ElementState::Pressed {
+                    self.buttons |= mask;
+                } else {
+                    self.buttons &= !mask;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_device_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta: (x, y) } = event {
+            if self.captured {
+                let sensitivity = self.settings.sensitivity as f64;
+                // the N64 mouse reports up as positive
+                self.motion.0 += x * sensitivity;
+                self.motion.1 -= y * sensitivity;
+            }
+        }
+    }
+
+    fn wants_pointer_capture(&self) -> bool {
+        self.captured
+    }
+
+    fn set_mouse_settings(&mut self, settings: &MouseSettings) {
+        self.settings = *settings;
+        self.set_captured(self.captured);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamepad::types::CONT_TYPE_MOUSE;
+
+    #[test]
+    fn test_relative_motion() {
+        let mut provider = MouseGamepadProvider::new();
+        provider.set_mouse_settings(&MouseSettings {
+            slot: Some(1),
+            sensitivity: 0.5,
+            ..Default::default()
+        });
+
+        let mouse = provider.scan().pop().unwrap();
+        assert_eq!(mouse.controller_type(), CONT_TYPE_MOUSE);
+
+        // motion is ignored until the pointer is captured
+        provider.handle_device_event(&DeviceEvent::MouseMotion { delta: (4.0, 0.0) });
+        provider.set_captured(true);
+        provider.handle_device_event(&DeviceEvent::MouseMotion { delta: (5.0, 4.0) });
+        provider.begin_poll();
+        assert_eq!(provider.read(&mouse).stick, Some((2, -2)));
+
+        // the leftover fraction adds up with the next poll
+        provider.handle_device_event(&DeviceEvent::MouseMotion { delta: (1.0, 0.0) });
+        provider.begin_poll();
+        assert_eq!(provider.read(&mouse).stick, Some((1, 0)));
+
+        provider.begin_poll();
+        assert_eq!(provider.read(&mouse).stick, Some((0, 0)));
+    }
+}
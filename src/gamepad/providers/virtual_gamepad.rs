@@ -0,0 +1,140 @@
+use crate::gamepad::providers::{
+    Gamepad, GamepadInput, GamepadProvider, GamepadService, HotplugEvent,
+};
+use crate::gamepad::types::{N64Button, MAXCONTROLLERS};
+use std::sync::{Arc, Mutex};
+
+/// The input a virtual gamepad reports on its port.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct VirtualPadState {
+    pub buttons: u16,
+    pub stick_x: i8,
+    pub stick_y: i8,
+}
+
+/// A handle to the virtual gamepads of a manager, it can be cloned and
+/// handed to whatever drives the input (tests, bots, scripts).
+#[derive(Debug, Default, Clone)]
+pub struct VirtualGamepads {
+    pads: Arc<Mutex<[Option<VirtualPadState>; MAXCONTROLLERS]>>,
+}
+
+impl VirtualGamepads {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Plugs a virtual gamepad into the port, it shows up on the next scan.
+    pub fn connect(&self, slot: usize) {
+        let mut pads = self.pads.lock().unwrap();
+        if let Some(pad) = pads.get_mut(slot) {
+            pad.get_or_insert_with(VirtualPadState::default);
+        }
+    }
+
+    pub fn disconnect(&self, slot: usize) {
+        let mut pads = self.pads.lock().unwrap();
+        if let Some(pad) = pads.get_mut(slot) {
+            *pad = None;
+        }
+    }
+
+    pub fn is_connected(&self, slot: usize) -> bool {
+        self.state(slot).is_some()
+    }
+
+    pub fn state(&self, slot: usize) -> Option<VirtualPadState> {
+        self.pads.lock().unwrap().get(slot).copied().flatten()
+    }
+
+    /// Replaces the state of a connected virtual gamepad.
+    pub fn set_state(&self, slot: usize, state: VirtualPadState) {
+        self.update(slot, |pad| *pad = state);
+    }
+
+    pub fn press(&self, slot: usize, button: N64Button) {
+        self.update(slot, |pad| pad.buttons |= button as u16);
+    }
+
+    pub fn release(&self, slot: usize, button: N64Button) {
+        self.update(slot, |pad| pad.buttons &= !(button as u16));
+    }
+
+    pub fn set_stick(&self, slot: usize, x: i8, y: i8) {
+        self.update(slot, |pad| {
+            pad.stick_x = x;
+            pad.stick_y = y;
+        });
+    }
+
+    fn update<F: FnOnce(&mut VirtualPadState)>(&self, slot: usize, f: F) {
+        let mut pads = self.pads.lock().unwrap();
+        if let Some(Some(pad)) = pads.get_mut(slot) {
+            f(pad);
+        }
+    }
+}
+
+pub struct VirtualGamepadProvider {
+    pub pads: VirtualGamepads,
+    reported: [bool; MAXCONTROLLERS],
+}
+
+impl VirtualGamepadProvider {
+    pub fn new(pads: VirtualGamepads) -> Self {
+        Self {
+            pads,
+            reported: [false; MAXCONTROLLERS],
+        }
+    }
+}
+
+impl GamepadProvider for VirtualGamepadProvider {
+    fn scan(&mut self) -> Vec<Gamepad> {
+        for (slot, reported) in self.reported.iter_mut().enumerate() {
+            *reported = self.pads.is_connected(slot);
+        }
+
+        (0..MAXCONTROLLERS)
+            .filter(|&slot| self.reported[slot])
+            .map(|slot| Gamepad::new(GamepadService::Virtual(slot as u8)))
+            .collect()
+    }
+
+    fn process_events(&mut self) -> Vec<HotplugEvent> {
+        let mut events = Vec::new();
+
+        for (slot, reported) in self.reported.iter_mut().enumerate() {
+            let connected = self.pads.is_connected(slot);
+            if connected == *reported {
+                continue;
+            }
+
+            let service = GamepadService::Virtual(slot as u8);
+            events.push(if connected {
+                HotplugEvent::Connected(Gamepad::new(service))
+            } else {
+                HotplugEvent::Disconnected(service)
+            });
+            *reported = connected;
+        }
+
+        events
+    }
+
+    fn read(&self, controller: &Gamepad) -> GamepadInput {
+        let GamepadService::Virtual(slot) = controller.service else {
+            return GamepadInput::default();
+        };
+
+        match self.pads.state(slot as usize) {
+            Some(state) => GamepadInput {
+                connected: true,
+                buttons: state.buttons,
+                stick: Some((state.stick_x, state.stick_y)),
+                triggers: (0.0, 0.0),
+            },
+            None => GamepadInput::default(),
+        }
+    }
+}
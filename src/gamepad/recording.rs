@@ -0,0 +1,215 @@
+use crate::gamepad::types::{OSControllerPad, MAXCONTROLLERS};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"HLXR";
+const FORMAT_VERSION: u8 = 1;
+const PAD_SIZE: usize = 5;
+
+/// Describes the session an input recording was made in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingHeader {
+    pub game_id: String,
+    pub helix_version: String,
+    pub port_count: u8,
+}
+
+impl RecordingHeader {
+    fn write<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[FORMAT_VERSION, self.port_count])?;
+        write_string(writer, &self.game_id)?;
+        write_string(writer, &self.helix_version)?;
+        Ok(())
+    }
+
+    fn read<R: Read>(reader: &mut R) -> anyhow::Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            anyhow::bail!("Not an input recording");
+        }
+
+        let mut info = [0u8; 2];
+        reader.read_exact(&mut info)?;
+        if info[0] != FORMAT_VERSION {
+            anyhow::bail!("Unsupported input recording version {}", info[0]);
+        }
+
+        let port_count = info[1];
+        if port_count as usize > MAXCONTROLLERS {
+            anyhow::bail!("Input recording has too many ports ({port_count})");
+        }
+
+        Ok(Self {
+            game_id: read_string(reader)?,
+            helix_version: read_string(reader)?,
+            port_count,
+        })
+    }
+}
+
+fn write_string<W: Write>(writer: &mut W, value: &str) -> anyhow::Result<()> {
+    let bytes = value.as_bytes();
+    let len = u8::try_from(bytes.len())?;
+    writer.write_all(&[len])?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+fn read_string<R: Read>(reader: &mut R) -> anyhow::Result<String> {
+    let mut len = [0u8; 1];
+    reader.read_exact(&mut len)?;
+    let mut bytes = vec![0u8; len[0] as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// Writes the pads of every poll to a file.
+pub struct InputRecorder {
+    writer: BufWriter<File>,
+    port_count: usize,
+    frame_count: usize,
+}
+
+impl InputRecorder {
+    pub fn create<P: AsRef<Path>>(path: P, game_id: &str) -> anyhow::Result<Self> {
+        let header = RecordingHeader {
+            game_id: game_id.to_string(),
+            helix_version: env!("CARGO_PKG_VERSION").to_string(),
+            port_count: MAXCONTROLLERS as u8,
+        };
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        header.write(&mut writer)?;
+
+        Ok(Self {
+            writer,
+            port_count: MAXCONTROLLERS,
+            frame_count: 0,
+        })
+    }
+
+    pub fn record_frame(&mut self, pads: &[OSControllerPad]) -> anyhow::Result<()> {
+        for pad in pads.iter().take(self.port_count) {
+            let [button_hi, button_lo] = pad.button.to_be_bytes();
+            self.writer.write_all(&[
+                button_hi,
+                button_lo,
+                pad.stick_x as u8,
+                pad.stick_y as u8,
+                pad.errno,
+            ])?;
+        }
+
+        self.frame_count += 1;
+        Ok(())
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    pub fn finish(mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Replays a recording made by `InputRecorder`, one frame per poll.
+pub struct InputPlayback {
+    header: RecordingHeader,
+    frames: Vec<[OSControllerPad; MAXCONTROLLERS]>,
+    position: usize,
+}
+
+impl InputPlayback {
+    pub fn open<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let header = RecordingHeader::read(&mut reader)?;
+
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        let frame_size = header.port_count as usize * PAD_SIZE;
+        if frame_size == 0 || data.len() % frame_size != 0 {
+            anyhow::bail!("Input recording is truncated");
+        }
+
+        let frames = data
+            .chunks(frame_size)
+            .map(|frame| {
+                let mut pads = [OSControllerPad::default(); MAXCONTROLLERS];
+                for (pad, bytes) in pads.iter_mut().zip(frame.chunks(PAD_SIZE)) {
+                    pad.button = u16::from_be_bytes([bytes[0], bytes[1]]);
+                    pad.stick_x = bytes[2] as i8;
+                    pad.stick_y = bytes[3] as i8;
+                    pad.errno = bytes[4];
+                }
+                pads
+            })
+            .collect();
+
+        Ok(Self {
+            header,
+            frames,
+            position: 0,
+        })
+    }
+
+    pub fn header(&self) -> &RecordingHeader {
+        &self.header
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the pads of the next frame, or `None` once the recording is over.
+    pub fn next_frame(&mut self) -> Option<&[OSControllerPad; MAXCONTROLLERS]> {
+        let frame = self.frames.get(self.position)?;
+        self.position += 1;
+        Some(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = std::env::temp_dir().join(format!("helix-recording-{}.bin", std::process::id()));
+
+        let mut pads = [OSControllerPad::default(); MAXCONTROLLERS];
+        let mut recorder = InputRecorder::create(&path, "NSME").unwrap();
+        for frame in 0..3 {
+            pads[1].button = frame;
+            pads[1].stick_x = -(frame as i8) * 10;
+            pads[3].errno = 0x8;
+            recorder.record_frame(&pads).unwrap();
+        }
+        recorder.finish().unwrap();
+
+        let mut playback = InputPlayback::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(playback.header().game_id, "NSME");
+        assert_eq!(playback.header().port_count, MAXCONTROLLERS as u8);
+        assert_eq!(playback.frame_count(), 3);
+
+        for frame in 0..3 {
+            let pads = playback.next_frame().unwrap();
+            assert_eq!(pads[1].button, frame);
+            assert_eq!(pads[1].stick_x, -(frame as i8) * 10);
+            assert_eq!(pads[3].errno, 0x8);
+        }
+
+        assert!(playback.next_frame().is_none());
+    }
+}
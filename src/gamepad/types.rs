@@ -1,5 +1,11 @@
+use serde::{Deserialize, Serialize};
+
 pub type GamepadBits = *mut u8;
 
+/// Number of controller ports available on the console.
+pub const MAXCONTROLLERS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum N64Button {
     A = 0x8000,
     B = 0x4000,
@@ -17,12 +23,58 @@ pub enum N64Button {
     DRight = 0x0100,
 }
 
+impl N64Button {
+    pub const ALL: [N64Button; 14] = [
+        N64Button::A,
+        N64Button::B,
+        N64Button::L,
+        N64Button::R,
+        N64Button::Z,
+        N64Button::Start,
+        N64Button::CUp,
+        N64Button::CDown,
+        N64Button::CLeft,
+        N64Button::CRight,
+        N64Button::DUp,
+        N64Button::DDown,
+        N64Button::DLeft,
+        N64Button::DRight,
+    ];
+
+    /// The button matching a single libultra button bit.
+    pub fn from_mask(mask: u16) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|button| *button as u16 == mask)
+    }
+}
+
 // MARK: - [Libultra] - C API
 
+pub const CONT_NO_RESPONSE_ERROR: u8 = 0x8;
+
+pub const CONT_TYPE_NORMAL: u16 = 0x0005;
+pub const CONT_TYPE_MOUSE: u16 = 0x0002;
+
+pub const CONT_CARD_ON: u8 = 0x01;
+
+pub const PFS_ERR_NOPACK: i32 = 1;
+pub const PFS_ERR_DEVICE: i32 = 11;
+
 #[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct OSControllerPad {
     pub button: u16,
     pub stick_x: i8, /* -80 <= stick_x <= 80 */
     pub stick_y: i8, /* -80 <= stick_x <= 80 */
     pub errno: u8,
 }
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OSContStatus {
+    pub type_: u16,
+    pub status: u8,
+    pub errno: u8,
+}
@@ -1,27 +1,96 @@
+use serde::{Deserialize, Serialize};
+
 pub const MAX_N64_AXIS_RANGE: f32 = 80.0;
-const DEADZONE_PERCENTAGE: f32 = 0.15;
+
+// An original controller reaches ~85 on the cardinals and ~69 per axis on the diagonals.
+const OCTAGON_DIAGONAL_RATIO: f32 = 69.0 / 85.0;
+
+/// Describes how raw stick values are shaped before being handed to the game.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StickSettings {
+    /// Fraction of the range around the center that is ignored.
+    pub inner_deadzone: f32,
+    /// Fraction of the range at the edge that is treated as full deflection.
+    pub outer_deadzone: f32,
+    /// Exponent applied to the deflection, 1.0 is linear.
+    pub response_curve: f32,
+    /// Multiplier applied to the final range.
+    pub range_scale: f32,
+    /// Whether values are limited to the octagonal gate of the N64 stick.
+    pub octagon_gate: bool,
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        Self {
+            inner_deadzone: 0.15,
+            outer_deadzone: 0.0,
+            response_curve: 1.0,
+            range_scale: 1.0,
+            octagon_gate: true,
+        }
+    }
+}
 
 fn normalize_stick_value(value: f32, max_range: f32) -> f32 {
     value * MAX_N64_AXIS_RANGE / max_range
 }
 
-fn limit_to_octagon(x: f32, y: f32) -> (f32, f32) {
-    // TODO: Implement this
-    (x, y)
+pub fn limit_to_octagon(x: f32, y: f32) -> (f32, f32) {
+    let radius = MAX_N64_AXIS_RANGE;
+    let diagonal = MAX_N64_AXIS_RANGE * OCTAGON_DIAGONAL_RATIO;
+
+    // fold the point into the first octant, where the gate edge runs
+    // from (radius, 0) to (diagonal, diagonal)
+    let (major, minor) = if x.abs() >= y.abs() {
+        (x.abs(), y.abs())
+    } else {
+        (y.abs(), x.abs())
+    };
+
+    let distance = diagonal * major + (radius - diagonal) * minor;
+    let limit = diagonal * radius;
+    if distance <= limit {
+        return (x, y);
+    }
+
+    let scale = limit / distance;
+    (x * scale, y * scale)
 }
 
-pub fn map_stick_value_to_n64(x: f32, y: f32, max_range: f32) -> Option<(i8, i8)> {
+pub fn map_stick_value_to_n64(
+    x: f32,
+    y: f32,
+    max_range: f32,
+    settings: &StickSettings,
+) -> Option<(i8, i8)> {
     let adjusted_x = normalize_stick_value(x, max_range);
     let adjusted_y = normalize_stick_value(y, max_range);
 
     // step 1: create deadzone circle area to discard faulty values
-    let magnitude = adjusted_x.powi(2) + adjusted_y.powi(2);
-    if magnitude.sqrt() <= (MAX_N64_AXIS_RANGE * DEADZONE_PERCENTAGE) {
+    let magnitude = (adjusted_x.powi(2) + adjusted_y.powi(2)).sqrt();
+    let inner = MAX_N64_AXIS_RANGE * settings.inner_deadzone;
+    if magnitude <= inner {
         return None;
     }
 
-    // Step 2: Limit values to points within an octagon
-    let (limited_x, limited_y) = limit_to_octagon(adjusted_x, adjusted_y);
+    // step 2: rescale the remaining range and apply the response curve
+    let outer = MAX_N64_AXIS_RANGE * (1.0 - settings.outer_deadzone);
+    let deflection = ((magnitude - inner) / (outer - inner).max(f32::EPSILON)).clamp(0.0, 1.0);
+    let scale =
+        deflection.powf(settings.response_curve) * settings.range_scale * MAX_N64_AXIS_RANGE
+            / magnitude;
+
+    let (scaled_x, scaled_y) = (adjusted_x * scale, adjusted_y * scale);
+
+    // step 3: limit values to points within an octagon
+    let (limited_x, limited_y) = if settings.octagon_gate {
+        limit_to_octagon(scaled_x, scaled_y)
+    } else {
+        (scaled_x, scaled_y)
+    };
+
     Some((limited_x as i8, limited_y as i8))
 }
 
@@ -37,4 +106,47 @@ mod tests {
         assert_eq!(normalize_stick_value(-0.5, 1.0), -40.0);
         assert_eq!(normalize_stick_value(-1.0, 1.0), -80.0);
     }
+
+    #[test]
+    fn test_limit_to_octagon() {
+        assert_eq!(limit_to_octagon(80.0, 0.0), (80.0, 0.0));
+        assert_eq!(limit_to_octagon(0.0, -80.0), (0.0, -80.0));
+        assert_eq!(limit_to_octagon(20.0, 20.0), (20.0, 20.0));
+
+        let (x, y) = limit_to_octagon(80.0, 80.0);
+        assert_eq!(x as i8, 64);
+        assert_eq!(y as i8, 64);
+
+        let (x, y) = limit_to_octagon(-80.0, 80.0);
+        assert_eq!(x as i8, -64);
+        assert_eq!(y as i8, 64);
+    }
+
+    #[test]
+    fn test_map_stick_value_to_n64() {
+        let settings = StickSettings::default();
+        assert_eq!(map_stick_value_to_n64(0.1, 0.1, 1.0, &settings), None);
+        assert_eq!(
+            map_stick_value_to_n64(1.0, 0.0, 1.0, &settings),
+            Some((80, 0))
+        );
+        assert_eq!(
+            map_stick_value_to_n64(0.0, -1.0, 1.0, &settings),
+            Some((0, -80))
+        );
+
+        let settings = StickSettings {
+            outer_deadzone: 0.1,
+            octagon_gate: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            map_stick_value_to_n64(0.95, 0.0, 1.0, &settings),
+            Some((80, 0))
+        );
+        assert_eq!(
+            map_stick_value_to_n64(1.0, 1.0, 1.0, &settings),
+            Some((56, 56))
+        );
+    }
 }
@@ -1,8 +1,19 @@
 use crate::gamepad::manager::GamepadManager;
 use fast3d::rdp::OutputDimensions;
 use fast3d::{RenderData, RCP};
+use frame_pacing::{FramePacer, FrameRate, FrameStats, GameLoopControl};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use winit::dpi::{LogicalPosition, LogicalSize, PhysicalPosition};
+use winit::event::{ElementState, ModifiersState, VirtualKeyCode, WindowEvent};
+use winit::monitor::MonitorHandle;
 use winit::platform::run_return::EventLoopExtRunReturn;
+use winit::window::{Fullscreen, WindowBuilder};
 
+pub mod frame_pacing;
 pub mod windows;
 
 #[cfg(feature = "opengl_renderer")]
@@ -19,10 +30,286 @@ pub use wgpu_renderer::Renderer;
 #[cfg(feature = "wgpu_renderer")]
 pub type Frame = wgpu::SurfaceTexture;
 
+const DEFAULT_WINDOW_STATE_PATH: &str = "window_state.json";
+const CLOSE_POPUP_ID: &str = "Quit?";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CloseState {
+    Open,
+    // the confirmation is opened on the next frame
+    ConfirmRequested,
+    Confirming,
+    Closing,
+}
+
 /// Represents the state of the UI.
 pub struct UIState {
-    last_frame_time: std::time::Instant,
+    frame_pacer: FramePacer,
     last_cursor: Option<imgui::MouseCursor>,
+    pointer_captured: bool,
+    modifiers: ModifiersState,
+    held_keys: Vec<VirtualKeyCode>,
+    // geometry of the window the last time it wasn't fullscreen
+    windowed_size: (u32, u32),
+    windowed_position: Option<(i32, i32)>,
+    // the mode used when switching to fullscreen
+    fullscreen_mode: FullscreenMode,
+    close_state: CloseState,
+}
+
+impl UIState {
+    /// Keeps track of the keyboard, returning the key and modifiers of a new
+    /// key press, repeats are ignored.
+    fn track_keyboard(&mut self, event: &WindowEvent) -> Option<Hotkey> {
+        match event {
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = *modifiers;
+                None
+            }
+            WindowEvent::KeyboardInput { input, .. } => {
+                let key = input.virtual_keycode?;
+                match input.state {
+                    ElementState::Pressed if !self.held_keys.contains(&key) => {
+                        self.held_keys.push(key);
+                        Some(Hotkey::new(key, self.modifiers))
+                    }
+                    ElementState::Pressed => None,
+                    ElementState::Released => {
+                        self.held_keys.retain(|held| *held != key);
+                        None
+                    }
+                }
+            }
+            WindowEvent::Focused(false) => {
+                self.held_keys.clear();
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A key pressed while holding exactly the given modifiers, e.g. Alt+Enter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hotkey {
+    pub key: VirtualKeyCode,
+    pub modifiers: ModifiersState,
+}
+
+impl Hotkey {
+    pub const fn new(key: VirtualKeyCode, modifiers: ModifiersState) -> Self {
+        Self { key, modifiers }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    ToggleFullscreen,
+    TogglePause,
+    AdvanceFrame,
+    ToggleFastForward,
+}
+
+/// The hotkeys handled by `Gui`, `None` turns one off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hotkeys {
+    pub fullscreen: Option<Hotkey>,
+    pub pause: Option<Hotkey>,
+    pub frame_advance: Option<Hotkey>,
+    pub fast_forward: Option<Hotkey>,
+}
+
+impl Hotkeys {
+    pub fn action(&self, pressed: Hotkey) -> Option<HotkeyAction> {
+        [
+            (self.fullscreen, HotkeyAction::ToggleFullscreen),
+            (self.pause, HotkeyAction::TogglePause),
+            (self.frame_advance, HotkeyAction::AdvanceFrame),
+            (self.fast_forward, HotkeyAction::ToggleFastForward),
+        ]
+        .into_iter()
+        .find(|(hotkey, _)| *hotkey == Some(pressed))
+        .map(|(_, action)| action)
+    }
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        let key = |key| Some(Hotkey::new(key, ModifiersState::empty()));
+
+        Self {
+            fullscreen: Some(Hotkey::new(VirtualKeyCode::Return, ModifiersState::ALT)),
+            pause: key(VirtualKeyCode::F5),
+            frame_advance: key(VirtualKeyCode::F6),
+            fast_forward: key(VirtualKeyCode::F7),
+        }
+    }
+}
+
+/// How the window covers the monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FullscreenMode {
+    Windowed,
+    /// A borderless window covering the whole monitor.
+    Borderless,
+    /// Takes over the monitor, switching to its video mode closest to the window size.
+    Exclusive,
+}
+
+/// How frames are handed over to the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresentMode {
+    /// Waits for the vertical blank, frames never tear.
+    Vsync,
+    /// Presents frames right away, they may tear.
+    Immediate,
+    /// Doesn't wait for the vertical blank but only shows whole frames, falls
+    /// back to vsync where it's not supported (always on OpenGL).
+    Mailbox,
+}
+
+/// Describes the window created by `Gui::new`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowConfig {
+    /// Size of the window in logical pixels, or of the video mode in exclusive fullscreen.
+    pub width: u32,
+    pub height: u32,
+    /// Position of the window in logical pixels, `None` centers it on the selected
+    /// monitor or lets the platform place it if there's none.
+    pub position: Option<(i32, i32)>,
+    pub fullscreen: FullscreenMode,
+    /// Index of the monitor to open the window on, `None` picks the primary one.
+    pub monitor: Option<usize>,
+    pub present_mode: PresentMode,
+    pub resizable: bool,
+    pub min_size: Option<(u32, u32)>,
+    /// Where the window geometry is saved on exit, the saved geometry takes
+    /// precedence over the config on the next launch.
+    pub state_path: Option<PathBuf>,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 600,
+            position: None,
+            fullscreen: FullscreenMode::Windowed,
+            monitor: None,
+            present_mode: PresentMode::Vsync,
+            resizable: true,
+            min_size: None,
+            state_path: Some(PathBuf::from(DEFAULT_WINDOW_STATE_PATH)),
+        }
+    }
+}
+
+impl WindowConfig {
+    /// The window described by the config, used by both renderers.
+    pub(crate) fn window_builder(
+        &self,
+        title: &str,
+        event_loop: &winit::event_loop::EventLoop<()>,
+    ) -> WindowBuilder {
+        let monitor = match self.monitor {
+            Some(index) => event_loop.available_monitors().nth(index).or_else(|| {
+                warn!("Monitor {index} not found, using the primary monitor");
+                event_loop.primary_monitor()
+            }),
+            None => event_loop.primary_monitor(),
+        };
+
+        let mut builder = WindowBuilder::new()
+            .with_title(title)
+            .with_inner_size(LogicalSize::new(self.width, self.height))
+            .with_resizable(self.resizable)
+            .with_fullscreen(self.fullscreen_for(monitor.clone()));
+
+        if let Some((width, height)) = self.min_size {
+            builder = builder.with_min_inner_size(LogicalSize::new(width, height));
+        }
+
+        match (self.position, monitor) {
+            (Some((x, y)), _) => builder = builder.with_position(LogicalPosition::new(x, y)),
+            (None, Some(monitor)) if self.monitor.is_some() => {
+                let size = LogicalSize::new(self.width, self.height)
+                    .to_physical::<i32>(monitor.scale_factor());
+                let (origin, area) = (monitor.position(), monitor.size());
+                builder = builder.with_position(PhysicalPosition::new(
+                    origin.x + (area.width as i32 - size.width) / 2,
+                    origin.y + (area.height as i32 - size.height) / 2,
+                ));
+            }
+            _ => {}
+        }
+
+        builder
+    }
+
+    /// The fullscreen state of the window on the monitor, `None` when windowed.
+    pub(crate) fn fullscreen_for(&self, monitor: Option<MonitorHandle>) -> Option<Fullscreen> {
+        match self.fullscreen {
+            FullscreenMode::Windowed => None,
+            FullscreenMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+            FullscreenMode::Exclusive => {
+                // the closest size wins, then the highest refresh rate and bit depth
+                let video_mode = monitor.as_ref().and_then(|monitor| {
+                    monitor.video_modes().min_by_key(|mode| {
+                        let size = mode.size();
+                        let distance = (size.width as i64 - self.width as i64).abs()
+                            + (size.height as i64 - self.height as i64).abs();
+                        (
+                            distance,
+                            std::cmp::Reverse(mode.refresh_rate_millihertz()),
+                            std::cmp::Reverse(mode.bit_depth()),
+                        )
+                    })
+                });
+
+                match video_mode {
+                    Some(video_mode) => Some(Fullscreen::Exclusive(video_mode)),
+                    None => {
+                        warn!("No video mode available for exclusive fullscreen, using borderless");
+                        Some(Fullscreen::Borderless(monitor))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The window geometry saved on exit and restored on the next launch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowState {
+    /// Size of the window when it's not fullscreen, in logical pixels.
+    pub width: u32,
+    pub height: u32,
+    pub position: Option<(i32, i32)>,
+    pub fullscreen: FullscreenMode,
+    pub monitor: Option<usize>,
+}
+
+impl WindowState {
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let state = serde_json::from_reader(reader)?;
+        Ok(state)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    /// Makes the config open the window the way it was saved.
+    pub fn apply(&self, config: &mut WindowConfig) {
+        config.width = self.width;
+        config.height = self.height;
+        config.position = self.position;
+        config.fullscreen = self.fullscreen;
+        config.monitor = self.monitor;
+    }
 }
 
 /// Wrapper around winit's event loop to allow for
@@ -53,6 +340,17 @@ pub struct Gui<'a> {
     // ui state
     ui_state: UIState,
 
+    // window
+    window_config: WindowConfig,
+    hotkeys: Hotkeys,
+
+    // game loop
+    game_loop: GameLoopControl,
+
+    // closing
+    confirm_close: bool,
+    close_callback: Option<Box<dyn Fn() + 'a>>,
+
     // draw callbacks
     draw_menu_callback: Box<dyn Fn(&imgui::Ui) + 'a>,
     draw_windows_callback: Box<dyn Fn(&imgui::Ui) + 'a>,
@@ -69,6 +367,7 @@ pub struct Gui<'a> {
 impl<'a> Gui<'a> {
     pub fn new<D, W>(
         title: &str,
+        config: &WindowConfig,
         event_loop_wrapper: &EventLoopWrapper,
         draw_menu: D,
         draw_windows: W,
@@ -103,21 +402,41 @@ impl<'a> Gui<'a> {
                 }),
             }]);
 
+        // Restore the window as it was on exit
+        let mut config = config.clone();
+        if let Some(path) = config.state_path.clone().filter(|path| path.exists()) {
+            match WindowState::load(path) {
+                Ok(state) => state.apply(&mut config),
+                Err(e) => warn!("Error loading window state: {e}"),
+            }
+        }
+
         // Setup Renderer
-        let (width, height) = (800, 600);
-        let renderer = Renderer::new(width, height, title, event_loop_wrapper, &mut imgui)?;
+        let renderer = Renderer::new(&config, title, event_loop_wrapper, &mut imgui)?;
         renderer.attach_window(&mut platform, &mut imgui);
 
-        // Initial UI state
-        let last_frame_time = std::time::Instant::now();
-
         Ok(Self {
             imgui,
             platform,
             ui_state: UIState {
-                last_frame_time,
+                frame_pacer: FramePacer::default(),
                 last_cursor: None,
+                pointer_captured: false,
+                modifiers: ModifiersState::empty(),
+                held_keys: Vec::new(),
+                windowed_size: (config.width, config.height),
+                windowed_position: config.position,
+                fullscreen_mode: match config.fullscreen {
+                    FullscreenMode::Windowed => FullscreenMode::Borderless,
+                    mode => mode,
+                },
+                close_state: CloseState::Open,
             },
+            window_config: config,
+            hotkeys: Hotkeys::default(),
+            game_loop: GameLoopControl::default(),
+            confirm_close: false,
+            close_callback: None,
             draw_menu_callback: Box::new(draw_menu),
             draw_windows_callback: Box::new(draw_windows),
             gamepad_manager,
@@ -128,6 +447,8 @@ impl<'a> Gui<'a> {
     }
 
     fn handle_events(&mut self, event_loop_wrapper: &mut EventLoopWrapper) {
+        let mut geometry_changed = false;
+
         event_loop_wrapper
             .event_loop
             .run_return(|event, _, control_flow| {
@@ -136,7 +457,7 @@ impl<'a> Gui<'a> {
                     winit::event::Event::WindowEvent {
                         event: winit::event::WindowEvent::CloseRequested,
                         ..
-                    } => std::process::exit(0),
+                    } => self.request_close(),
                     winit::event::Event::WindowEvent {
                         event:
                             winit::event::WindowEvent::Resized(size)
@@ -146,6 +467,7 @@ impl<'a> Gui<'a> {
                             },
                         ..
                     } => {
+                        geometry_changed = true;
                         self.gfx_renderer.resize(size.width, size.height);
 
                         // TODO: Fix resizing on OpenGL
@@ -154,22 +476,34 @@ impl<'a> Gui<'a> {
                             .handle_event(&mut self.platform, &mut self.imgui, &event);
                     }
                     winit::event::Event::WindowEvent {
-                        event: winit::event::WindowEvent::ModifiersChanged(modifiers),
+                        event: ref window_event,
                         ..
                     } => {
+                        // hotkeys aren't seen by the game
+                        let action = self
+                            .ui_state
+                            .track_keyboard(window_event)
+                            .and_then(|pressed| self.hotkeys.action(pressed));
+                        if let Some(action) = action {
+                            self.run_hotkey_action(action);
+                            return;
+                        }
+
+                        geometry_changed |= matches!(window_event, WindowEvent::Moved(_));
+
                         if let Some(gamepad_manager) = self.gamepad_manager.as_mut() {
-                            gamepad_manager.handle_modifiers_changed(modifiers);
+                            gamepad_manager.handle_window_event(window_event);
                         }
 
                         self.gfx_renderer
                             .handle_event(&mut self.platform, &mut self.imgui, &event);
                     }
-                    winit::event::Event::WindowEvent {
-                        event: winit::event::WindowEvent::KeyboardInput { input, .. },
+                    winit::event::Event::DeviceEvent {
+                        event: ref device_event,
                         ..
                     } => {
                         if let Some(gamepad_manager) = self.gamepad_manager.as_mut() {
-                            gamepad_manager.handle_keyboard_input(input);
+                            gamepad_manager.handle_device_event(device_event);
                         }
 
                         self.gfx_renderer
@@ -181,34 +515,253 @@ impl<'a> Gui<'a> {
                     }
                 }
             });
+
+        if geometry_changed {
+            self.update_windowed_geometry();
+        }
+
+        self.update_pointer_capture();
     }
 
-    fn sync_frame_rate(&mut self) {
-        const FRAME_INTERVAL_MS: u64 = 1000 / 30;
+    /// Remembers the size and position of the window while it isn't fullscreen,
+    /// they're the ones saved when leaving from fullscreen.
+    fn update_windowed_geometry(&mut self) {
+        let geometry = self.gfx_renderer.with_window(|window| {
+            if window.fullscreen().is_some() {
+                return None;
+            }
+
+            let scale_factor = window.scale_factor();
+            let size = window.inner_size().to_logical::<u32>(scale_factor);
+            let position = window
+                .outer_position()
+                .ok()
+                .map(|position| position.to_logical::<i32>(scale_factor))
+                .map(|position| (position.x, position.y));
+
+            Some(((size.width, size.height), position))
+        });
+
+        if let Some((size, position)) = geometry {
+            self.ui_state.windowed_size = size;
+            // not every platform reports the position
+            if position.is_some() {
+                self.ui_state.windowed_position = position;
+            }
+        }
+    }
 
-        let frame_duration = self.ui_state.last_frame_time.elapsed();
-        if frame_duration < std::time::Duration::from_millis(FRAME_INTERVAL_MS) {
-            let sleep_duration =
-                std::time::Duration::from_millis(FRAME_INTERVAL_MS) - frame_duration;
-            spin_sleep::sleep(sleep_duration);
+    fn run_hotkey_action(&mut self, action: HotkeyAction) {
+        match action {
+            HotkeyAction::ToggleFullscreen => self.toggle_fullscreen(),
+            HotkeyAction::TogglePause => self.game_loop.toggle_pause(),
+            HotkeyAction::AdvanceFrame => self.game_loop.advance_frame(),
+            HotkeyAction::ToggleFastForward => self.game_loop.toggle_fast_forward(),
         }
+    }
 
-        let now = std::time::Instant::now();
+    fn update_pointer_capture(&mut self) {
+        let captured = self
+            .gamepad_manager
+            .as_ref()
+            .is_some_and(|gamepad_manager| gamepad_manager.wants_pointer_capture());
 
-        self.imgui
-            .io_mut()
-            .update_delta_time(now - self.ui_state.last_frame_time);
+        if captured != self.ui_state.pointer_captured {
+            self.ui_state.pointer_captured = captured;
+            self.gfx_renderer.set_cursor_captured(captured);
+        }
+    }
 
-        self.ui_state.last_frame_time = now;
+    fn sync_frame_rate(&mut self) {
+        self.ui_state.frame_pacer.set_speed(self.game_loop.speed());
+        let frame_time = self.ui_state.frame_pacer.wait();
+        self.imgui.io_mut().update_delta_time(frame_time);
     }
 
     pub fn renderer_name(&self) -> String {
         self.gfx_renderer.name()
     }
 
+    // Frame Pacing Methods
+
+    pub fn frame_rate(&self) -> FrameRate {
+        self.ui_state.frame_pacer.rate()
+    }
+
+    /// Sets the rate `end_frame` paces frames at, 30 Hz by default.
+    pub fn set_frame_rate(&mut self, rate: FrameRate) {
+        self.ui_state.frame_pacer.set_rate(rate);
+    }
+
+    /// The measured time of the last frames.
+    pub fn frame_stats(&self) -> &FrameStats {
+        self.ui_state.frame_pacer.stats()
+    }
+
+    // Game Loop Methods
+
+    /// Whether the game should run its logic this frame, false while paused
+    /// (unless stepping a frame). The UI is drawn either way.
+    pub fn should_run_game_frame(&self) -> bool {
+        self.game_loop.should_run_game_frame()
+    }
+
+    pub fn game_loop(&self) -> &GameLoopControl {
+        &self.game_loop
+    }
+
+    /// Pauses, steps and fast-forwards the game.
+    pub fn game_loop_mut(&mut self) -> &mut GameLoopControl {
+        &mut self.game_loop
+    }
+
+    pub fn hotkeys(&self) -> &Hotkeys {
+        &self.hotkeys
+    }
+
+    /// Sets the hotkeys, by default Alt+Enter toggles fullscreen, F5 pauses,
+    /// F6 advances a frame and F7 toggles fast-forward.
+    pub fn set_hotkeys(&mut self, hotkeys: Hotkeys) {
+        self.hotkeys = hotkeys;
+    }
+
+    // Close Methods
+
+    /// Whether the window was closed, the game should leave its loop and drop
+    /// the `Gui` then.
+    pub fn should_close(&self) -> bool {
+        self.ui_state.close_state == CloseState::Closing
+    }
+
+    /// Closes the window like its close button does, asking first if enabled.
+    pub fn request_close(&mut self) {
+        match self.ui_state.close_state {
+            CloseState::Open if self.confirm_close => {
+                self.ui_state.close_state = CloseState::ConfirmRequested;
+            }
+            CloseState::Open => self.close(),
+            _ => {}
+        }
+    }
+
+    /// Takes back a close, e.g. to let the player save first.
+    pub fn cancel_close(&mut self) {
+        self.ui_state.close_state = CloseState::Open;
+    }
+
+    /// Asks "are you sure?" before closing, off by default.
+    pub fn set_confirm_close(&mut self, confirm: bool) {
+        self.confirm_close = confirm;
+    }
+
+    /// Sets a callback called when the window is closed.
+    pub fn set_close_callback<F: Fn() + 'static>(&mut self, callback: F) {
+        self.close_callback = Some(Box::new(callback));
+    }
+
+    pub fn clear_close_callback(&mut self) {
+        self.close_callback = None;
+    }
+
+    fn close(&mut self) {
+        self.ui_state.close_state = CloseState::Closing;
+        if let Some(callback) = self.close_callback.as_ref() {
+            callback();
+        }
+    }
+
+    // Window Methods
+
+    pub fn is_fullscreen(&self) -> bool {
+        self.gfx_renderer
+            .with_window(|window| window.fullscreen().is_some())
+    }
+
+    /// Switches the window between windowed and fullscreen, on the monitor it's on.
+    /// Fullscreen uses the mode of the config, borderless if it opened windowed.
+    pub fn set_fullscreen(&mut self, fullscreen: bool) {
+        if fullscreen == self.is_fullscreen() {
+            return;
+        }
+
+        if fullscreen {
+            self.update_windowed_geometry();
+        }
+
+        let config = WindowConfig {
+            fullscreen: if fullscreen {
+                self.ui_state.fullscreen_mode
+            } else {
+                FullscreenMode::Windowed
+            },
+            ..self.window_config.clone()
+        };
+
+        self.gfx_renderer.with_window(|window| {
+            window.set_fullscreen(config.fullscreen_for(window.current_monitor()));
+        });
+    }
+
+    pub fn toggle_fullscreen(&mut self) {
+        self.set_fullscreen(!self.is_fullscreen());
+    }
+
+    /// The size of the window content, in logical pixels.
+    pub fn window_size(&self) -> (u32, u32) {
+        self.gfx_renderer.with_window(|window| {
+            let size = window.inner_size().to_logical::<u32>(window.scale_factor());
+            (size.width, size.height)
+        })
+    }
+
+    /// Resizes the window content, in logical pixels. While fullscreen it's the
+    /// size the window gets back when leaving it.
+    pub fn set_window_size(&mut self, width: u32, height: u32) {
+        self.ui_state.windowed_size = (width, height);
+        self.gfx_renderer
+            .with_window(|window| window.set_inner_size(LogicalSize::new(width, height)));
+    }
+
+    /// The state of the window as it would be saved on exit.
+    pub fn window_state(&self) -> WindowState {
+        let (fullscreen, monitor) = self.gfx_renderer.with_window(|window| {
+            let fullscreen = match window.fullscreen() {
+                None => FullscreenMode::Windowed,
+                Some(Fullscreen::Borderless(_)) => FullscreenMode::Borderless,
+                Some(Fullscreen::Exclusive(_)) => FullscreenMode::Exclusive,
+            };
+            let monitor = window.current_monitor().and_then(|current| {
+                window
+                    .available_monitors()
+                    .position(|monitor| monitor == current)
+            });
+
+            (fullscreen, monitor)
+        });
+
+        let (width, height) = self.ui_state.windowed_size;
+        WindowState {
+            width,
+            height,
+            position: self.ui_state.windowed_position,
+            fullscreen,
+            monitor,
+        }
+    }
+
+    /// Saves the window state to the `state_path` of the config, if any.
+    pub fn save_window_state(&self) -> anyhow::Result<()> {
+        if let Some(path) = self.window_config.state_path.as_ref() {
+            self.window_state().save(path)?;
+        }
+
+        Ok(())
+    }
+
     pub fn start_frame(&mut self, event_loop_wrapper: &mut EventLoopWrapper) -> anyhow::Result<()> {
         // Handle events
         self.handle_events(event_loop_wrapper);
+        self.game_loop.begin_frame();
 
         // Prepare for drawing
         self.gfx_renderer
@@ -237,6 +790,7 @@ impl<'a> Gui<'a> {
         let ui = self.imgui.new_frame();
         ui.main_menu_bar(|| (self.draw_menu_callback)(ui));
         (self.draw_windows_callback)(ui);
+        let confirmed = Self::draw_close_confirmation(ui, &mut self.ui_state.close_state);
 
         if self.ui_state.last_cursor != ui.mouse_cursor() {
             self.ui_state.last_cursor = ui.mouse_cursor();
@@ -252,18 +806,144 @@ impl<'a> Gui<'a> {
         // Swap buffers
         self.gfx_renderer.finish_render(frame)?;
 
+        if confirmed {
+            self.close();
+        }
+
         Ok(())
     }
 
+    /// Draws the "are you sure?" popup, returns whether the close was confirmed.
+    fn draw_close_confirmation(ui: &imgui::Ui, close_state: &mut CloseState) -> bool {
+        match close_state {
+            CloseState::ConfirmRequested => {
+                ui.open_popup(CLOSE_POPUP_ID);
+                *close_state = CloseState::Confirming;
+            }
+            CloseState::Confirming => {}
+            _ => return false,
+        }
+
+        let mut confirmed = false;
+        let shown = ui
+            .modal_popup_config(CLOSE_POPUP_ID)
+            .always_auto_resize(true)
+            .build(|| {
+                ui.text("Are you sure you want to quit?");
+
+                if ui.button("Quit") {
+                    confirmed = true;
+                    ui.close_current_popup();
+                }
+                ui.same_line();
+                if ui.button("Cancel") {
+                    *close_state = CloseState::Open;
+                    ui.close_current_popup();
+                }
+            });
+
+        // the popup can also be closed from elsewhere
+        if shown.is_none() {
+            *close_state = CloseState::Open;
+        }
+
+        confirmed
+    }
+
     pub fn end_frame(&mut self) {
         self.sync_frame_rate();
     }
 }
 
+impl Drop for Gui<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = self.save_window_state() {
+            warn!("Error saving window state: {e}");
+        }
+    }
+}
+
 // MARK: - C API
 
 type OnDrawUi = unsafe extern "C" fn(ui: &imgui::Ui);
 
+/// C representation of `WindowConfig`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CWindowConfig {
+    pub width: u32,
+    pub height: u32,
+    pub has_position: bool,
+    pub x: i32,
+    pub y: i32,
+    /// 0 windowed, 1 borderless, 2 exclusive.
+    pub fullscreen: i32,
+    /// -1 picks the primary monitor.
+    pub monitor: i32,
+    /// 0 vsync, 1 immediate, 2 mailbox.
+    pub present_mode: i32,
+    pub resizable: bool,
+    /// 0 leaves the size unconstrained.
+    pub min_width: u32,
+    pub min_height: u32,
+    /// Saves the window state to `window_state.json` on exit and restores it on launch.
+    pub save_state: bool,
+}
+
+impl From<&CWindowConfig> for WindowConfig {
+    fn from(config: &CWindowConfig) -> Self {
+        Self {
+            width: config.width,
+            height: config.height,
+            position: config.has_position.then_some((config.x, config.y)),
+            fullscreen: match config.fullscreen {
+                1 => FullscreenMode::Borderless,
+                2 => FullscreenMode::Exclusive,
+                _ => FullscreenMode::Windowed,
+            },
+            monitor: usize::try_from(config.monitor).ok(),
+            present_mode: match config.present_mode {
+                1 => PresentMode::Immediate,
+                2 => PresentMode::Mailbox,
+                _ => PresentMode::Vsync,
+            },
+            resizable: config.resizable,
+            min_size: (config.min_width > 0 && config.min_height > 0)
+                .then_some((config.min_width, config.min_height)),
+            state_path: config
+                .save_state
+                .then(|| PathBuf::from(DEFAULT_WINDOW_STATE_PATH)),
+        }
+    }
+}
+
+impl From<&WindowConfig> for CWindowConfig {
+    fn from(config: &WindowConfig) -> Self {
+        let (x, y) = config.position.unwrap_or_default();
+        let (min_width, min_height) = config.min_size.unwrap_or_default();
+
+        Self {
+            width: config.width,
+            height: config.height,
+            has_position: config.position.is_some(),
+            x,
+            y,
+            fullscreen: config.fullscreen as i32,
+            monitor: config.monitor.map_or(-1, |monitor| monitor as i32),
+            present_mode: config.present_mode as i32,
+            resizable: config.resizable,
+            min_width,
+            min_height,
+            save_state: config.state_path.is_some(),
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn GUIDefaultWindowConfig() -> CWindowConfig {
+    CWindowConfig::from(&WindowConfig::default())
+}
+
 #[no_mangle]
 pub extern "C" fn GUICreateEventLoop() -> Box<EventLoopWrapper> {
     let event_loop = EventLoopWrapper::default();
@@ -273,6 +953,7 @@ pub extern "C" fn GUICreateEventLoop() -> Box<EventLoopWrapper> {
 #[no_mangle]
 pub unsafe extern "C" fn GUICreate<'a>(
     title_raw: *const i8,
+    config: Option<&CWindowConfig>,
     event_loop: Option<&'a mut EventLoopWrapper>,
     draw_menu: Option<OnDrawUi>,
     draw_windows: Option<OnDrawUi>,
@@ -281,9 +962,13 @@ pub unsafe extern "C" fn GUICreate<'a>(
     let title_str: &std::ffi::CStr = unsafe { std::ffi::CStr::from_ptr(title_raw) };
     let title: &str = std::str::from_utf8(title_str.to_bytes()).unwrap();
 
+    // no config opens the default window
+    let config = config.map(WindowConfig::from).unwrap_or_default();
+
     let event_loop = event_loop.unwrap();
     let gui = Gui::new(
         title,
+        &config,
         event_loop,
         move |ui| unsafe {
             if let Some(draw_menu) = draw_menu {
@@ -302,6 +987,20 @@ pub unsafe extern "C" fn GUICreate<'a>(
     Box::new(gui)
 }
 
+#[no_mangle]
+pub extern "C" fn GUIFree(gui: Option<Box<Gui>>) {
+    if let Some(gui) = gui {
+        drop(gui);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn GUIFreeEventLoop(event_loop: Option<Box<EventLoopWrapper>>) {
+    if let Some(event_loop) = event_loop {
+        drop(event_loop);
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn GUIStartFrame(gui: Option<&mut Gui>, event_loop: Option<&mut EventLoopWrapper>) {
     let gui = gui.unwrap();
@@ -327,3 +1026,165 @@ pub extern "C" fn GUIGetAspectRatio(gui: Option<&mut Gui>) -> f32 {
     let gui = gui.unwrap();
     gui.rcp.rdp.output_dimensions.aspect_ratio
 }
+
+/// Frame time stats in milliseconds, see `FrameStats`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CFrameStats {
+    pub last: f32,
+    pub average: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+#[no_mangle]
+pub extern "C" fn GUISetFrameRate(gui: Option<&mut Gui>, rate: f64) {
+    let gui = gui.unwrap();
+    // 0 or less turns the cap off
+    let rate = if rate > 0.0 {
+        FrameRate::Target(rate)
+    } else {
+        FrameRate::Uncapped
+    };
+    gui.set_frame_rate(rate);
+}
+
+#[no_mangle]
+pub extern "C" fn GUIGetFrameRate(gui: Option<&mut Gui>) -> f64 {
+    let gui = gui.unwrap();
+    match gui.frame_rate() {
+        FrameRate::Target(rate) => rate,
+        FrameRate::Uncapped => 0.0,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn GUIGetFrameStats(gui: Option<&mut Gui>) -> CFrameStats {
+    let gui = gui.unwrap();
+    let stats = gui.frame_stats();
+    CFrameStats {
+        last: stats.last(),
+        average: stats.average(),
+        min: stats.min(),
+        max: stats.max(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn GUIShouldRunGameFrame(gui: Option<&mut Gui>) -> bool {
+    let gui = gui.unwrap();
+    gui.should_run_game_frame()
+}
+
+#[no_mangle]
+pub extern "C" fn GUISetPaused(gui: Option<&mut Gui>, paused: bool) {
+    let gui = gui.unwrap();
+    gui.game_loop_mut().set_paused(paused);
+}
+
+#[no_mangle]
+pub extern "C" fn GUIIsPaused(gui: Option<&mut Gui>) -> bool {
+    let gui = gui.unwrap();
+    gui.game_loop().is_paused()
+}
+
+#[no_mangle]
+pub extern "C" fn GUIAdvanceFrame(gui: Option<&mut Gui>) {
+    let gui = gui.unwrap();
+    gui.game_loop_mut().advance_frame();
+}
+
+#[no_mangle]
+pub extern "C" fn GUISetFastForward(gui: Option<&mut Gui>, fast_forward: bool) {
+    let gui = gui.unwrap();
+    gui.game_loop_mut().set_fast_forward(fast_forward);
+}
+
+#[no_mangle]
+pub extern "C" fn GUIIsFastForwarding(gui: Option<&mut Gui>) -> bool {
+    let gui = gui.unwrap();
+    gui.game_loop().is_fast_forwarding()
+}
+
+#[no_mangle]
+pub extern "C" fn GUISetFastForwardSpeed(gui: Option<&mut Gui>, speed: f64) {
+    let gui = gui.unwrap();
+    gui.game_loop_mut().set_fast_forward_speed(speed);
+}
+
+#[no_mangle]
+pub extern "C" fn GUIShouldClose(gui: Option<&mut Gui>) -> bool {
+    let gui = gui.unwrap();
+    gui.should_close()
+}
+
+#[no_mangle]
+pub extern "C" fn GUIRequestClose(gui: Option<&mut Gui>) {
+    let gui = gui.unwrap();
+    gui.request_close();
+}
+
+#[no_mangle]
+pub extern "C" fn GUICancelClose(gui: Option<&mut Gui>) {
+    let gui = gui.unwrap();
+    gui.cancel_close();
+}
+
+#[no_mangle]
+pub extern "C" fn GUISetConfirmClose(gui: Option<&mut Gui>, confirm: bool) {
+    let gui = gui.unwrap();
+    gui.set_confirm_close(confirm);
+}
+
+#[no_mangle]
+pub extern "C" fn GUIToggleFullscreen(gui: Option<&mut Gui>) {
+    let gui = gui.unwrap();
+    gui.toggle_fullscreen();
+}
+
+#[no_mangle]
+pub extern "C" fn GUISetFullscreen(gui: Option<&mut Gui>, fullscreen: bool) {
+    let gui = gui.unwrap();
+    gui.set_fullscreen(fullscreen);
+}
+
+#[no_mangle]
+pub extern "C" fn GUIIsFullscreen(gui: Option<&mut Gui>) -> bool {
+    let gui = gui.unwrap();
+    gui.is_fullscreen()
+}
+
+#[no_mangle]
+pub extern "C" fn GUIGetWindowSize(
+    gui: Option<&mut Gui>,
+    width: Option<&mut u32>,
+    height: Option<&mut u32>,
+) {
+    let gui = gui.unwrap();
+    let size = gui.window_size();
+
+    if let Some(width) = width {
+        *width = size.0;
+    }
+    if let Some(height) = height {
+        *height = size.1;
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn GUISetWindowSize(gui: Option<&mut Gui>, width: u32, height: u32) {
+    let gui = gui.unwrap();
+    gui.set_window_size(width, height);
+}
+
+#[no_mangle]
+pub extern "C" fn GUISaveWindowState(gui: Option<&mut Gui>) -> i32 {
+    let gui = gui.unwrap();
+    match gui.save_window_state() {
+        Ok(()) => 0,
+        Err(e) => {
+            warn!("Error saving window state: {e}");
+            -1
+        }
+    }
+}
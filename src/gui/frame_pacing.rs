@@ -0,0 +1,382 @@
+use std::time::{Duration, Instant};
+
+/// Number of frames kept by `FrameStats`.
+pub const FRAME_HISTORY: usize = 120;
+
+/// Default multiplier of the frame rate while fast-forwarding.
+pub const DEFAULT_FAST_FORWARD_SPEED: f64 = 4.0;
+
+/// How often `Gui::end_frame` lets a new frame start.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameRate {
+    /// Waits so frames start at the given rate, in Hz.
+    Target(f64),
+    /// Doesn't wait, only the present mode (e.g. vsync) limits the rate.
+    Uncapped,
+}
+
+impl FrameRate {
+    pub const HZ_20: Self = Self::Target(20.0);
+    pub const HZ_30: Self = Self::Target(30.0);
+    /// The rate of PAL games.
+    pub const HZ_50: Self = Self::Target(50.0);
+    pub const HZ_60: Self = Self::Target(60.0);
+
+    /// The time between two frames, `None` when uncapped.
+    pub fn interval(&self) -> Option<Duration> {
+        match *self {
+            Self::Target(hz) if hz.is_finite() && hz > 0.0 => {
+                Some(Duration::from_secs_f64(1.0 / hz))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Default for FrameRate {
+    fn default() -> Self {
+        Self::HZ_30
+    }
+}
+
+/// The measured time of the last `FRAME_HISTORY` frames, in milliseconds.
+#[derive(Debug, Clone)]
+pub struct FrameStats {
+    times: [f32; FRAME_HISTORY],
+    next: usize,
+    count: usize,
+}
+
+impl FrameStats {
+    pub fn new() -> Self {
+        Self {
+            times: [0.0; FRAME_HISTORY],
+            next: 0,
+            count: 0,
+        }
+    }
+
+    pub fn push(&mut self, frame_time: Duration) {
+        self.times[self.next] = frame_time.as_secs_f32() * 1000.0;
+        self.next = (self.next + 1) % FRAME_HISTORY;
+        self.count = (self.count + 1).min(FRAME_HISTORY);
+    }
+
+    /// The recorded frame times and the index of the oldest one, the buffer
+    /// wraps around once it's full.
+    pub fn history(&self) -> (&[f32], usize) {
+        if self.count < FRAME_HISTORY {
+            (&self.times[..self.count], 0)
+        } else {
+            (&self.times, self.next)
+        }
+    }
+
+    pub fn last(&self) -> f32 {
+        match self.count {
+            0 => 0.0,
+            _ => self.times[(self.next + FRAME_HISTORY - 1) % FRAME_HISTORY],
+        }
+    }
+
+    pub fn average(&self) -> f32 {
+        let (times, _) = self.history();
+        match times.len() {
+            0 => 0.0,
+            len => times.iter().sum::<f32>() / len as f32,
+        }
+    }
+
+    pub fn min(&self) -> f32 {
+        let (times, _) = self.history();
+        times.iter().copied().reduce(f32::min).unwrap_or(0.0)
+    }
+
+    pub fn max(&self) -> f32 {
+        let (times, _) = self.history();
+        times.iter().copied().reduce(f32::max).unwrap_or(0.0)
+    }
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Paces frames against a schedule of deadlines rather than the length of the
+/// previous frame, so the rate holds without drifting to whole milliseconds.
+pub struct FramePacer {
+    rate: FrameRate,
+    speed: f64,
+    next_deadline: Option<Instant>,
+    last_frame: Instant,
+    stats: FrameStats,
+}
+
+impl FramePacer {
+    pub fn new(rate: FrameRate) -> Self {
+        Self {
+            rate,
+            speed: 1.0,
+            next_deadline: None,
+            last_frame: Instant::now(),
+            stats: FrameStats::new(),
+        }
+    }
+
+    pub fn rate(&self) -> FrameRate {
+        self.rate
+    }
+
+    pub fn set_rate(&mut self, rate: FrameRate) {
+        self.rate = rate;
+        self.next_deadline = None;
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Multiplies the frame rate, e.g. 2 runs twice as many frames.
+    pub fn set_speed(&mut self, speed: f64) {
+        if speed > 0.0 && speed != self.speed {
+            self.speed = speed;
+            self.next_deadline = None;
+        }
+    }
+
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    /// The time between two frames at the current speed, `None` when uncapped.
+    pub fn interval(&self) -> Option<Duration> {
+        self.rate
+            .interval()
+            .map(|interval| interval.div_f64(self.speed))
+    }
+
+    /// Waits until the next frame is due and returns the time since the previous one.
+    pub fn wait(&mut self) -> Duration {
+        if let Some(interval) = self.interval() {
+            let deadline = self.next_deadline.unwrap_or(self.last_frame + interval);
+            let (sleep, next_deadline) = schedule(deadline, Instant::now(), interval);
+            if !sleep.is_zero() {
+                spin_sleep::sleep(sleep);
+            }
+
+            self.next_deadline = Some(next_deadline);
+        }
+
+        let now = Instant::now();
+        let frame_time = now - self.last_frame;
+        self.last_frame = now;
+        self.stats.push(frame_time);
+
+        frame_time
+    }
+}
+
+impl Default for FramePacer {
+    fn default() -> Self {
+        Self::new(FrameRate::default())
+    }
+}
+
+/// Returns how long to wait for the frame due at `deadline` and when the next one is due.
+fn schedule(deadline: Instant, now: Instant, interval: Duration) -> (Duration, Instant) {
+    if now < deadline {
+        (deadline - now, deadline + interval)
+    } else if now - deadline < interval {
+        // slightly late, the next frame makes up for it
+        (Duration::ZERO, deadline + interval)
+    } else {
+        // too far behind to catch up without a burst of frames
+        (Duration::ZERO, now + interval)
+    }
+}
+
+/// Pauses, steps and fast-forwards the game loop, the UI keeps running meanwhile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameLoopControl {
+    paused: bool,
+    pending_steps: u32,
+    run_frame: bool,
+    fast_forward: bool,
+    fast_forward_speed: f64,
+}
+
+impl GameLoopControl {
+    pub fn new() -> Self {
+        Self {
+            paused: false,
+            pending_steps: 0,
+            run_frame: true,
+            fast_forward: false,
+            fast_forward_speed: DEFAULT_FAST_FORWARD_SPEED,
+        }
+    }
+
+    /// Decides whether the game runs during the frame that's starting.
+    pub fn begin_frame(&mut self) {
+        self.run_frame = !self.paused || self.pending_steps > 0;
+        self.pending_steps = self.pending_steps.saturating_sub(1);
+    }
+
+    /// Whether the game should run its logic this frame, the answer holds
+    /// until the next frame starts.
+    pub fn should_run_game_frame(&self) -> bool {
+        self.run_frame
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.pending_steps = 0;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.set_paused(!self.paused);
+    }
+
+    /// Runs exactly one more game frame, pausing the game first if needed.
+    pub fn advance_frame(&mut self) {
+        if !self.paused {
+            self.set_paused(true);
+        }
+        self.pending_steps += 1;
+    }
+
+    pub fn is_fast_forwarding(&self) -> bool {
+        self.fast_forward
+    }
+
+    pub fn set_fast_forward(&mut self, fast_forward: bool) {
+        self.fast_forward = fast_forward;
+    }
+
+    pub fn toggle_fast_forward(&mut self) {
+        self.fast_forward = !self.fast_forward;
+    }
+
+    pub fn fast_forward_speed(&self) -> f64 {
+        self.fast_forward_speed
+    }
+
+    /// Sets the multiplier of the frame rate while fast-forwarding.
+    pub fn set_fast_forward_speed(&mut self, speed: f64) {
+        if speed > 0.0 {
+            self.fast_forward_speed = speed;
+        }
+    }
+
+    /// The multiplier of the frame rate, the UI runs at the regular rate while paused.
+    pub fn speed(&self) -> f64 {
+        if self.fast_forward && !self.paused {
+            self.fast_forward_speed
+        } else {
+            1.0
+        }
+    }
+}
+
+impl Default for GameLoopControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_rate_interval() {
+        assert_eq!(
+            FrameRate::HZ_60.interval(),
+            Some(Duration::from_secs_f64(1.0 / 60.0))
+        );
+        assert_eq!(FrameRate::Target(0.0).interval(), None);
+        assert_eq!(FrameRate::Uncapped.interval(), None);
+    }
+
+    #[test]
+    fn test_frame_stats() {
+        let mut stats = FrameStats::new();
+        assert_eq!((stats.average(), stats.last()), (0.0, 0.0));
+
+        for ms in [10, 20, 30] {
+            stats.push(Duration::from_millis(ms));
+        }
+        assert_eq!(stats.history(), (&[10.0, 20.0, 30.0][..], 0));
+        assert_eq!((stats.min(), stats.max()), (10.0, 30.0));
+        assert_eq!((stats.average(), stats.last()), (20.0, 30.0));
+
+        // once full the oldest frames are overwritten
+        for _ in 0..FRAME_HISTORY {
+            stats.push(Duration::from_millis(5));
+        }
+        assert_eq!(stats.history().1, 3);
+        assert_eq!((stats.min(), stats.max()), (5.0, 5.0));
+    }
+
+    #[test]
+    fn test_schedule_holds_rate() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let interval = ms(5);
+
+        // frames that take 3 ms sleep the rest, the deadlines don't drift
+        let mut deadline = start + interval;
+        for frame in 1..=20 {
+            let now = deadline - interval + ms(3);
+            let (sleep, next) = schedule(deadline, now, interval);
+            assert_eq!(sleep, ms(2));
+            assert_eq!(deadline, start + interval * frame);
+            deadline = next;
+        }
+
+        // a late frame doesn't wait and the next one makes up for it
+        assert_eq!(
+            schedule(deadline, deadline + ms(2), interval),
+            (Duration::ZERO, deadline + interval)
+        );
+
+        // a frame that's far behind starts the schedule over
+        assert_eq!(
+            schedule(deadline, deadline + ms(12), interval),
+            (Duration::ZERO, deadline + ms(17))
+        );
+    }
+
+    #[test]
+    fn test_pause_and_frame_advance() {
+        let mut control = GameLoopControl::new();
+        let run_frames = |control: &mut GameLoopControl, frames| {
+            (0..frames)
+                .filter(|_| {
+                    control.begin_frame();
+                    control.should_run_game_frame()
+                })
+                .count()
+        };
+
+        assert_eq!(run_frames(&mut control, 3), 3);
+
+        // each step runs a single frame, then the game stays paused
+        control.advance_frame();
+        control.advance_frame();
+        assert!(control.is_paused());
+        assert_eq!(run_frames(&mut control, 5), 2);
+
+        control.set_fast_forward(true);
+        assert_eq!(control.speed(), 1.0);
+        control.toggle_pause();
+        assert_eq!(control.speed(), DEFAULT_FAST_FORWARD_SPEED);
+        assert_eq!(run_frames(&mut control, 2), 2);
+    }
+}
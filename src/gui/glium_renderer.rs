@@ -1,4 +1,4 @@
-use crate::gui::{EventLoopWrapper, Frame};
+use crate::gui::{EventLoopWrapper, Frame, PresentMode, WindowConfig};
 use fast3d::RenderData;
 use fast3d_glium_renderer::GliumRenderer;
 
@@ -10,21 +10,19 @@ pub struct Renderer<'a> {
 
 impl<'a> Renderer<'a> {
     pub fn new(
-        width: i32,
-        height: i32,
+        config: &WindowConfig,
         title: &str,
         event_loop_wrapper: &EventLoopWrapper,
         imgui: &mut imgui::Context,
     ) -> anyhow::Result<Self> {
         // Create the window
-        let build = glutin::window::WindowBuilder::new()
-            .with_title(title)
-            .with_inner_size(glutin::dpi::LogicalSize::new(width, height));
+        let build = config.window_builder(title, &event_loop_wrapper.event_loop);
 
+        // OpenGL can't present without waiting unless it may tear, so mailbox waits too
         let context = glutin::ContextBuilder::new()
             .with_depth_buffer(24)
             .with_gl(glutin::GlRequest::Latest)
-            .with_vsync(true);
+            .with_vsync(config.present_mode != PresentMode::Immediate);
 
         let display = glium::Display::new(build, context, &event_loop_wrapper.event_loop)?;
 
@@ -82,6 +80,34 @@ impl<'a> Renderer<'a> {
         platform.prepare_render(ui, self.display.gl_window().window());
     }
 
+    // Window Functions
+
+    /// Runs `f` with the window, which is borrowed from the display.
+    pub fn with_window<R>(&self, f: impl FnOnce(&glutin::window::Window) -> R) -> R {
+        let gl_window = self.display.gl_window();
+        f(gl_window.window())
+    }
+
+    pub fn set_cursor_captured(&self, captured: bool) {
+        let gl_window = self.display.gl_window();
+        let window = gl_window.window();
+
+        let result = if captured {
+            // not every platform can lock the cursor in place, confine it to the window instead
+            window
+                .set_cursor_grab(glutin::window::CursorGrabMode::Locked)
+                .or_else(|_| window.set_cursor_grab(glutin::window::CursorGrabMode::Confined))
+        } else {
+            window.set_cursor_grab(glutin::window::CursorGrabMode::None)
+        };
+
+        if let Err(e) = result {
+            log::warn!("Error changing cursor grab: {e}");
+        }
+
+        window.set_cursor_visible(!captured);
+    }
+
     // Rendering Functions
 
     pub fn name(&self) -> String {
@@ -1,4 +1,4 @@
-use crate::gui::{EventLoopWrapper, Frame};
+use crate::gui::{EventLoopWrapper, Frame, PresentMode, WindowConfig};
 use fast3d::RenderData;
 
 use fast3d_wgpu_renderer::WgpuRenderer;
@@ -27,6 +27,22 @@ fn create_depth_texture(
     depth_texture.create_view(&wgpu::TextureViewDescriptor::default())
 }
 
+/// Picks the present mode if the surface supports it, or else Fifo, which every surface supports.
+fn select_present_mode(mode: PresentMode, supported: &[wgpu::PresentMode]) -> wgpu::PresentMode {
+    let preferred = match mode {
+        PresentMode::Vsync => wgpu::PresentMode::Fifo,
+        PresentMode::Immediate => wgpu::PresentMode::Immediate,
+        PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+    };
+
+    if supported.contains(&preferred) {
+        preferred
+    } else {
+        log::warn!("Present mode {preferred:?} is not supported, using vsync");
+        wgpu::PresentMode::Fifo
+    }
+}
+
 pub struct Renderer<'a> {
     window: winit::window::Window,
     surface: wgpu::Surface,
@@ -41,8 +57,7 @@ pub struct Renderer<'a> {
 
 impl<'a> Renderer<'a> {
     pub fn new(
-        width: i32,
-        height: i32,
+        config: &WindowConfig,
         title: &str,
         event_loop_wrapper: &EventLoopWrapper,
         imgui: &mut imgui::Context,
@@ -55,10 +70,8 @@ impl<'a> Renderer<'a> {
 
         // Create the window
         let (window, size, surface) = {
-            let window = winit::window::WindowBuilder::new()
-                .with_title(title)
-                .with_inner_size(winit::dpi::LogicalSize::new(width, height))
-                .with_resizable(true)
+            let window = config
+                .window_builder(title, &event_loop_wrapper.event_loop)
                 .build(&event_loop_wrapper.event_loop)?;
 
             let size = window.inner_size();
@@ -91,6 +104,10 @@ impl<'a> Renderer<'a> {
             .get_default_config(&adapter, size.width, size.height)
             .ok_or(anyhow::anyhow!("Failed to get default surface config"))?;
         surface_config.format = wgpu::TextureFormat::Bgra8Unorm;
+        surface_config.present_mode = select_present_mode(
+            config.present_mode,
+            &surface.get_capabilities(&adapter).present_modes,
+        );
 
         surface.configure(&device, &surface_config);
 
@@ -162,6 +179,32 @@ impl<'a> Renderer<'a> {
         platform.prepare_render(ui, &self.window);
     }
 
+    // Window Functions
+
+    /// Runs `f` with the window.
+    pub fn with_window<R>(&self, f: impl FnOnce(&winit::window::Window) -> R) -> R {
+        f(&self.window)
+    }
+
+    pub fn set_cursor_captured(&self, captured: bool) {
+        let window = &self.window;
+
+        let result = if captured {
+            // not every platform can lock the cursor in place, confine it to the window instead
+            window
+                .set_cursor_grab(winit::window::CursorGrabMode::Locked)
+                .or_else(|_| window.set_cursor_grab(winit::window::CursorGrabMode::Confined))
+        } else {
+            window.set_cursor_grab(winit::window::CursorGrabMode::None)
+        };
+
+        if let Err(e) = result {
+            log::warn!("Error changing cursor grab: {e}");
+        }
+
+        window.set_cursor_visible(!captured);
+    }
+
     // Rendering Functions
 
     pub fn name(&self) -> String {
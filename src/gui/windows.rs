@@ -1,11 +1,72 @@
 use fast3d::fast3d_gbi::defines::GeometryModes;
 
-use imgui::{CollapsingHeader, Ui};
+use imgui::{CollapsingHeader, Condition, Ui};
+use log::warn;
 
+use crate::gamepad::manager::GamepadManager;
+use crate::gamepad::mapping::{N64Input, StickDirection};
+use crate::gamepad::types::{N64Button, OSControllerPad, CONT_NO_RESPONSE_ERROR, MAXCONTROLLERS};
+use crate::gamepad::utils::{limit_to_octagon, MAX_N64_AXIS_RANGE};
 use crate::gui::Gui;
 
+const DEFAULT_MAPPING_PROFILE_PATH: &str = "mapping_profile.json";
+
+const STICK_VIEW_SIZE: f32 = 120.0;
+const GATE_COLOR: [f32; 4] = [0.6, 0.6, 0.6, 1.0];
+const STICK_COLOR: [f32; 4] = [0.2, 0.7, 1.0, 1.0];
+const PRESSED_COLOR: [f32; 4] = [0.3, 0.9, 0.3, 1.0];
+const RELEASED_COLOR: [f32; 4] = [0.4, 0.4, 0.4, 1.0];
+
+const OVERLAY_STICK_SIZE: f32 = 64.0;
+const OVERLAY_MARGIN: f32 = 10.0;
+const OVERLAY_LAYOUT: [&[N64Button]; 4] = [
+    &[N64Button::L, N64Button::Z, N64Button::R, N64Button::Start],
+    &[N64Button::A, N64Button::B],
+    &[
+        N64Button::CUp,
+        N64Button::CDown,
+        N64Button::CLeft,
+        N64Button::CRight,
+    ],
+    &[
+        N64Button::DUp,
+        N64Button::DDown,
+        N64Button::DLeft,
+        N64Button::DRight,
+    ],
+];
+
+/// Where the input display sits on the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputDisplayPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    /// Starts in the top left corner and can be dragged around.
+    Free,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputDisplaySettings {
+    pub position: InputDisplayPosition,
+    /// Opacity of the background, from 0 (transparent) to 1.
+    pub opacity: f32,
+}
+
+impl Default for InputDisplaySettings {
+    fn default() -> Self {
+        Self {
+            position: InputDisplayPosition::BottomLeft,
+            opacity: 0.5,
+        }
+    }
+}
+
 pub trait HelixWindows {
     fn show_profiler_window(&self, opened: &mut bool, gui: &mut Gui);
+    fn show_controller_window(&self, opened: &mut bool, gui: &mut Gui);
+    fn show_input_display(&self, opened: &mut bool, gui: &mut Gui, settings: &InputDisplaySettings);
 }
 
 impl HelixWindows for Ui {
@@ -23,6 +84,36 @@ impl HelixWindows for Ui {
                     self.io().framerate
                 ));
 
+                let target = match gui.frame_rate().interval() {
+                    Some(interval) => format!("{:.3} ms", interval.as_secs_f64() * 1000.0),
+                    None => "uncapped".to_string(),
+                };
+                let stats = gui.frame_stats();
+                self.text(format!(
+                    "Frame time {:.3} ms (min {:.3}, max {:.3}), target {}",
+                    stats.last(),
+                    stats.min(),
+                    stats.max(),
+                    target
+                ));
+
+                let game_loop = gui.game_loop();
+                if game_loop.is_paused() {
+                    self.text("Game paused");
+                } else if game_loop.is_fast_forwarding() {
+                    self.text(format!(
+                        "Fast-forwarding at {:.1}x",
+                        game_loop.fast_forward_speed()
+                    ));
+                }
+
+                let (times, offset) = stats.history();
+                self.plot_lines("##frame_times", times)
+                    .values_offset(offset)
+                    .scale_min(0.0)
+                    .graph_size([0.0, 40.0])
+                    .build();
+
                 let total_tris = gui
                     .render_data
                     .draw_calls
@@ -114,6 +205,302 @@ impl HelixWindows for Ui {
                 }
             });
     }
+
+    fn show_controller_window(&self, opened: &mut bool, gui: &mut Gui) {
+        self.window("Controllers")
+            .opened(opened)
+            .always_auto_resize(true)
+            .build(|| match gui.gamepad_manager.as_deref_mut() {
+                Some(manager) => {
+                    show_ports(self, manager);
+                    self.separator();
+                    show_mapping_profile(self, manager);
+                }
+                None => self.text("No gamepad manager attached"),
+            });
+    }
+
+    fn show_input_display(
+        &self,
+        opened: &mut bool,
+        gui: &mut Gui,
+        settings: &InputDisplaySettings,
+    ) {
+        let Some(manager) = gui.gamepad_manager.as_deref() else {
+            return;
+        };
+
+        let [width, height] = self.io().display_size;
+        // keep clear of the main menu bar
+        let top = OVERLAY_MARGIN + self.frame_height();
+        let (position, pivot) = match settings.position {
+            InputDisplayPosition::TopLeft | InputDisplayPosition::Free => {
+                ([OVERLAY_MARGIN, top], [0.0, 0.0])
+            }
+            InputDisplayPosition::TopRight => ([width - OVERLAY_MARGIN, top], [1.0, 0.0]),
+            InputDisplayPosition::BottomLeft => {
+                ([OVERLAY_MARGIN, height - OVERLAY_MARGIN], [0.0, 1.0])
+            }
+            InputDisplayPosition::BottomRight => (
+                [width - OVERLAY_MARGIN, height - OVERLAY_MARGIN],
+                [1.0, 1.0],
+            ),
+        };
+
+        let window = self
+            .window("Input Display")
+            .opened(opened)
+            .no_decoration()
+            .no_nav()
+            .always_auto_resize(true)
+            .focus_on_appearing(false)
+            .bg_alpha(settings.opacity.clamp(0.0, 1.0))
+            .position_pivot(pivot);
+
+        // pinned overlays let clicks through to what's below them
+        let window = match settings.position {
+            InputDisplayPosition::Free => window.position(position, Condition::FirstUseEver),
+            _ => window.position(position, Condition::Always).no_inputs(),
+        };
+
+        window.build(|| {
+            let pads = manager.last_pads();
+            let mut shown = 0;
+
+            for (slot, pad) in pads.iter().enumerate() {
+                if pad.errno == CONT_NO_RESPONSE_ERROR {
+                    continue;
+                }
+
+                if shown > 0 {
+                    self.same_line();
+                }
+                shown += 1;
+
+                self.group(|| {
+                    self.text(format!("P{}", slot + 1));
+                    draw_stick(self, pad, OVERLAY_STICK_SIZE);
+                    self.same_line();
+                    self.group(|| draw_overlay_buttons(self, pad));
+                });
+            }
+
+            if shown == 0 {
+                self.text_disabled("No controllers");
+            }
+        });
+    }
+}
+
+fn show_ports(ui: &Ui, manager: &GamepadManager) {
+    for slot in 0..MAXCONTROLLERS {
+        let devices = manager
+            .gamepads()
+            .iter()
+            .filter(|gamepad| gamepad.slot as usize == slot)
+            .map(|gamepad| match &gamepad.profile {
+                Some(profile) => format!("{} ({profile})", gamepad.name),
+                None => gamepad.name.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        if devices.is_empty() {
+            ui.text_disabled(format!("Port {}: Not connected", slot + 1));
+            continue;
+        }
+
+        let pad = &manager.last_pads()[slot];
+        ui.tree_node_config(format!("Port {}: {}", slot + 1, devices.join(", ")))
+            .build(|| show_pad(ui, pad));
+    }
+}
+
+fn show_pad(ui: &Ui, pad: &OSControllerPad) {
+    if pad.errno == CONT_NO_RESPONSE_ERROR {
+        ui.text_disabled("No response");
+        return;
+    }
+
+    draw_stick(ui, pad, STICK_VIEW_SIZE);
+
+    ui.same_line();
+    ui.group(|| {
+        ui.text(format!("Stick: {:4} {:4}", pad.stick_x, pad.stick_y));
+        for (index, button) in N64Button::ALL.iter().enumerate() {
+            let color = if pad.button & *button as u16 != 0 {
+                PRESSED_COLOR
+            } else {
+                RELEASED_COLOR
+            };
+
+            if index % 4 != 0 {
+                ui.same_line();
+            }
+            ui.text_colored(color, format!("{:<6}", format!("{button:?}")));
+        }
+    });
+}
+
+/// Draws the stick position within the octagonal gate.
+fn draw_stick(ui: &Ui, pad: &OSControllerPad, size: f32) {
+    let origin = ui.cursor_screen_pos();
+    let half = size / 2.0;
+    let center = [origin[0] + half, origin[1] + half];
+    let scale = half / MAX_N64_AXIS_RANGE;
+    let to_screen = |x: f32, y: f32| [center[0] + x * scale, center[1] - y * scale];
+
+    // the gate corners are where the edges of the octagon meet the cardinals and diagonals
+    let mut gate = (0..=8)
+        .map(|corner| {
+            let angle = (corner as f32 * 45.0).to_radians();
+            let far = MAX_N64_AXIS_RANGE * 2.0;
+            let (x, y) = limit_to_octagon(angle.cos() * far, angle.sin() * far);
+            to_screen(x, y)
+        })
+        .collect::<Vec<_>>();
+    gate.dedup();
+
+    let draw_list = ui.get_window_draw_list();
+    draw_list.add_polyline(gate, GATE_COLOR).build();
+    draw_list
+        .add_line(
+            to_screen(-MAX_N64_AXIS_RANGE, 0.0),
+            to_screen(MAX_N64_AXIS_RANGE, 0.0),
+            RELEASED_COLOR,
+        )
+        .build();
+    draw_list
+        .add_line(
+            to_screen(0.0, -MAX_N64_AXIS_RANGE),
+            to_screen(0.0, MAX_N64_AXIS_RANGE),
+            RELEASED_COLOR,
+        )
+        .build();
+    draw_list
+        .add_circle(
+            to_screen(pad.stick_x as f32, pad.stick_y as f32),
+            4.0,
+            STICK_COLOR,
+        )
+        .filled(true)
+        .build();
+    ui.dummy([size, size]);
+}
+
+/// Draws the buttons laid out by group, pressed ones in the color of the N64 controller.
+fn draw_overlay_buttons(ui: &Ui, pad: &OSControllerPad) {
+    for row in OVERLAY_LAYOUT {
+        for (index, button) in row.iter().enumerate() {
+            if index > 0 {
+                ui.same_line();
+            }
+
+            let color = if pad.button & *button as u16 != 0 {
+                button_color(*button)
+            } else {
+                RELEASED_COLOR
+            };
+            ui.text_colored(color, button_label(*button));
+        }
+    }
+}
+
+fn button_label(button: N64Button) -> &'static str {
+    match button {
+        N64Button::A => "A",
+        N64Button::B => "B",
+        N64Button::Z => "Z",
+        N64Button::Start => "S",
+        N64Button::L => "L",
+        N64Button::R => "R",
+        N64Button::CUp => "C^",
+        N64Button::CDown => "Cv",
+        N64Button::CLeft => "C<",
+        N64Button::CRight => "C>",
+        N64Button::DUp => "D^",
+        N64Button::DDown => "Dv",
+        N64Button::DLeft => "D<",
+        N64Button::DRight => "D>",
+    }
+}
+
+fn button_color(button: N64Button) -> [f32; 4] {
+    match button {
+        N64Button::A => [0.25, 0.45, 1.0, 1.0],
+        N64Button::B => [0.2, 0.8, 0.3, 1.0],
+        N64Button::Start => [0.95, 0.25, 0.25, 1.0],
+        N64Button::CUp | N64Button::CDown | N64Button::CLeft | N64Button::CRight => {
+            [1.0, 0.85, 0.1, 1.0]
+        }
+        _ => [0.95, 0.95, 0.95, 1.0],
+    }
+}
+
+fn show_mapping_profile(ui: &Ui, manager: &mut GamepadManager) {
+    if !CollapsingHeader::new("Mapping Profile").build(ui) {
+        return;
+    }
+
+    ui.text(format!("Profile: {}", manager.mapping_profile().name));
+
+    let inputs = N64Button::ALL
+        .iter()
+        .map(|button| N64Input::Button(*button))
+        .chain(
+            StickDirection::ALL
+                .iter()
+                .map(|direction| N64Input::Stick(*direction)),
+        );
+
+    for input in inputs {
+        let label = match input {
+            N64Input::Button(button) => format!("{button:?}"),
+            N64Input::Stick(direction) => format!("Stick {direction:?}"),
+        };
+
+        let bound = manager
+            .mapping_profile()
+            .buttons_for(input)
+            .map(|button| format!("{button:?}"))
+            .collect::<Vec<_>>();
+
+        let _id = ui.push_id(label.as_str());
+        ui.text(format!("{label:<12}"));
+        ui.same_line_with_pos(100.0);
+
+        if manager.pending_binding() == Some(input) {
+            ui.text_colored(PRESSED_COLOR, "Press a button...");
+            ui.same_line();
+            if ui.small_button("Cancel") {
+                manager.cancel_binding();
+            }
+        } else {
+            if ui.small_button("Bind") {
+                manager.start_binding(input);
+            }
+            ui.same_line();
+            if bound.is_empty() {
+                ui.text_disabled("Unbound");
+            } else {
+                ui.text(bound.join(", "));
+            }
+        }
+    }
+
+    ui.separator();
+
+    let path = manager
+        .mapping_profile_path()
+        .map(|path| path.to_path_buf())
+        .unwrap_or_else(|| DEFAULT_MAPPING_PROFILE_PATH.into());
+
+    if ui.button("Save") {
+        if let Err(e) = manager.save_mapping_profile(&path) {
+            warn!("Error saving mapping profile: {e}");
+        }
+    }
+    ui.same_line();
+    ui.text_disabled(path.display().to_string());
 }
 
 // MARK: - C API
@@ -128,3 +515,44 @@ pub extern "C" fn GUIShowProfilerWindow(ui: &Ui, gui: Option<&mut Gui>, opened:
 
     ui.show_profiler_window(opened, gui);
 }
+
+#[no_mangle]
+pub extern "C" fn GUIShowControllerWindow(
+    ui: &Ui,
+    gui: Option<&mut Gui>,
+    opened: Option<&mut bool>,
+) {
+    let opened = opened.unwrap();
+    let gui = gui.unwrap();
+    if !*opened {
+        return;
+    }
+
+    ui.show_controller_window(opened, gui);
+}
+
+/// `position` is one of `InputDisplayPosition` in order, anything else pins it to the bottom left.
+#[no_mangle]
+pub extern "C" fn GUIShowInputDisplay(
+    ui: &Ui,
+    gui: Option<&mut Gui>,
+    opened: Option<&mut bool>,
+    position: i32,
+    opacity: f32,
+) {
+    let opened = opened.unwrap();
+    let gui = gui.unwrap();
+    if !*opened {
+        return;
+    }
+
+    let position = match position {
+        0 => InputDisplayPosition::TopLeft,
+        1 => InputDisplayPosition::TopRight,
+        3 => InputDisplayPosition::BottomRight,
+        4 => InputDisplayPosition::Free,
+        _ => InputDisplayPosition::BottomLeft,
+    };
+
+    ui.show_input_display(opened, gui, &InputDisplaySettings { position, opacity });
+}